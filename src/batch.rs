@@ -0,0 +1,96 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::bitboard::Bitboard;
+use crate::sudoku::Sudoku;
+
+/// Result of solving a single puzzle.
+pub struct Outcome {
+    pub solution: Option<[u8; 81]>,
+    pub steps: u64,
+    pub guesses: u64,
+    pub time: Duration,
+}
+
+/// Solves the puzzle with the bitboard solver. Its steps are the digits placed during the search.
+pub fn solve_bitboard(sudoku: &Sudoku) -> Outcome {
+    let start = Instant::now();
+    let mut board = Bitboard::new(&sudoku.tiles);
+    let solution = board.as_mut().and_then(|board| board.solve());
+    Outcome {
+        solution,
+        steps: board.as_ref().map_or(0, |board| board.nodes),
+        guesses: board.as_ref().map_or(0, |board| board.guesses),
+        time: start.elapsed(),
+    }
+}
+
+/// Number of threads used by default, one per core.
+pub fn default_threads() -> usize {
+    std::thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+/// Threads used for `items` items when asked for `threads`: at least one, and no more
+/// than there are items.
+pub fn thread_count(threads: usize, items: usize) -> usize {
+    threads.clamp(1, items.max(1))
+}
+
+/// Runs `solve` for every item on `threads` threads and returns the results in the order
+/// of the items, no matter which thread finishes first. `progress` is called on the
+/// calling thread every now and then with the number of finished items.
+pub fn run<P: Sync, T: Send>(items: &[P], threads: usize, solve: impl Fn(&P) -> T + Sync, mut progress: impl FnMut(usize)) -> Vec<T> {
+    let next = AtomicUsize::new(0);
+    let finished = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<T>>> = Mutex::new((0..items.len()).map(|_| None).collect());
+    std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..thread_count(threads, items.len())).map(|_| {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                let Some(item) = items.get(i) else {
+                    break;
+                };
+                let result = solve(item);
+                results.lock().unwrap()[i] = Some(result);
+                finished.fetch_add(1, Ordering::Relaxed);
+            })
+        }).collect();
+        let mut reported = Instant::now();
+        progress(0);
+        loop {
+            let done = finished.load(Ordering::Relaxed);
+            // A panicking worker would never finish its item, the panic is passed on by the scope.
+            if done == items.len() || workers.iter().all(|worker| worker.is_finished()) {
+                progress(done);
+                break;
+            }
+            if reported.elapsed() >= Duration::from_millis(100) {
+                progress(done);
+                reported = Instant::now();
+            }
+            std::thread::sleep(Duration::from_millis(5));
+        }
+    });
+    results.into_inner().unwrap().into_iter().map(Option::unwrap).collect()
+}
+
+/// Smallest, median and largest of a set of values.
+pub struct Summary<T> {
+    pub min: T,
+    pub median: T,
+    pub max: T,
+}
+
+impl<T: Ord + Copy> Summary<T> {
+    /// Returns `None` for no values.
+    pub fn of(values: impl Iterator<Item = T>) -> Option<Self> {
+        let mut values: Vec<T> = values.collect();
+        values.sort_unstable();
+        Some(Summary {
+            min: *values.first()?,
+            median: values[values.len() / 2],
+            max: *values.last()?,
+        })
+    }
+}
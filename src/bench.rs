@@ -0,0 +1,216 @@
+use std::time::{Duration, Instant};
+
+use crate::batch::{self, Outcome, Summary};
+use crate::dlx;
+use crate::sat::{self, Cdcl};
+use crate::solver::SolverKind;
+use crate::sudoku::{SolverState, Sudoku};
+
+/// Puzzle collections benchmarked when no files are given.
+pub const DATASETS: [&str; 4] = [
+    "input/Sudoku_easy.sdm",
+    "input/Sudoku_medium.sdm",
+    "input/Sudoku_hard.sdm",
+    "input/Top_50K_Toughest.sdm",
+];
+
+/// Steps the animated solvers are advanced by before the step limit is checked again.
+const CHUNK: u64 = 1000;
+
+/// A way of solving puzzles that can be benchmarked.
+#[derive(Clone, Copy, Debug)]
+pub enum Strategy {
+    Bitboard,
+    Sat,
+    Animated(SolverKind),
+}
+
+impl Strategy {
+    pub const ALL: [Strategy; 6] = [
+        Strategy::Bitboard,
+        Strategy::Sat,
+        Strategy::Animated(SolverKind::Backtracking),
+        Strategy::Animated(SolverKind::DancingLinks),
+        Strategy::Animated(SolverKind::Annealing),
+        Strategy::Animated(SolverKind::Genetic),
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Strategy::Bitboard => "bitboard",
+            Strategy::Sat => "sat",
+            Strategy::Animated(SolverKind::Backtracking) => "backtracking",
+            Strategy::Animated(SolverKind::DancingLinks) => "dlx",
+            Strategy::Animated(SolverKind::Annealing) => "annealing",
+            Strategy::Animated(SolverKind::Genetic) => "genetic",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Strategy::ALL.into_iter().find(|strategy| strategy.name() == name)
+    }
+
+    /// Steps after which an animated solver gives up by default, each allows for about a
+    /// second on a hard puzzle. The other strategies always run until they are done.
+    pub fn default_max_steps(self) -> u64 {
+        match self {
+            Strategy::Animated(SolverKind::Backtracking) => 10_000_000,
+            Strategy::Animated(SolverKind::DancingLinks) => 10_000_000,
+            Strategy::Animated(SolverKind::Annealing) => 5_000_000,
+            Strategy::Animated(SolverKind::Genetic) => 2_500,
+            Strategy::Bitboard | Strategy::Sat => u64::MAX,
+        }
+    }
+
+    /// What the steps and the guesses of an outcome count for this strategy.
+    pub fn counters(self) -> (&'static str, &'static str) {
+        match self {
+            Strategy::Bitboard => ("placements", "guesses"),
+            Strategy::Sat => ("conflicts", "decisions"),
+            Strategy::Animated(SolverKind::Backtracking | SolverKind::DancingLinks) => ("steps", "backtracks"),
+            Strategy::Animated(SolverKind::Annealing) => ("swaps", "reheats"),
+            Strategy::Animated(SolverKind::Genetic) => ("generations", "renewals"),
+        }
+    }
+
+    /// Solves the puzzle. The steps and guesses are the counters named by `counters`, the
+    /// animated solvers give up after `max_steps` steps.
+    pub fn solve(self, sudoku: &Sudoku, max_steps: u64) -> Outcome {
+        match self {
+            Strategy::Bitboard => batch::solve_bitboard(sudoku),
+            Strategy::Sat => {
+                let start = Instant::now();
+                let mut solver = Cdcl::new(&sat::encode(&sudoku.tiles, &dlx::standard_houses()));
+                let solution = solver.solve().map(|model| sat::decode(&model));
+                Outcome {
+                    solution,
+                    steps: solver.conflicts,
+                    guesses: solver.decisions,
+                    time: start.elapsed(),
+                }
+            }
+            Strategy::Animated(kind) => {
+                let start = Instant::now();
+                let mut sudoku = sudoku.clone();
+                // Without a trace, nothing is rewound here and recording it would only cost time.
+                let mut solver = kind.create(&sudoku.tiles, sudoku.seed, 0);
+                while matches!(solver.state(), SolverState::Running) && solver.stats().steps < max_steps {
+                    let steps = CHUNK.min(max_steps - solver.stats().steps);
+                    solver.advance(&mut sudoku.tiles, steps as u32, &[]);
+                }
+                let solution = match solver.state() {
+                    SolverState::SolutionFound => Some(sudoku.to_digits()),
+                    _ => None,
+                };
+                // The stochastic solvers never backtrack, they start over when they get stuck.
+                let guesses = match kind {
+                    SolverKind::Annealing | SolverKind::Genetic => solver.stats().restarts,
+                    SolverKind::Backtracking | SolverKind::DancingLinks => solver.stats().backtracks,
+                };
+                Outcome {
+                    solution,
+                    steps: solver.stats().steps,
+                    guesses,
+                    time: start.elapsed(),
+                }
+            }
+        }
+    }
+}
+
+/// Picks `count` puzzles spread evenly over the collection, always the same ones.
+pub fn sample(puzzles: &[Sudoku], count: usize) -> Vec<Sudoku> {
+    let count = count.min(puzzles.len());
+    (0..count).map(|k| puzzles[k * puzzles.len() / count].clone()).collect()
+}
+
+/// Results of one strategy on one puzzle collection, a line of the report.
+pub struct Measurement {
+    pub dataset: String,
+    pub strategy: Strategy,
+    pub puzzles: usize,
+    pub solved: usize,
+    pub steps: Summary<u64>,
+    pub steps_mean: f64,
+    pub guesses_mean: f64,
+    pub time: Summary<Duration>,
+    pub time_mean: Duration,
+    /// Time for the whole collection, all threads together.
+    pub wall: Duration,
+}
+
+impl Measurement {
+    /// `solved` is the number of correct solutions among the outcomes. `None` for no outcomes.
+    pub fn new(dataset: &str, strategy: Strategy, outcomes: &[Outcome], solved: usize, wall: Duration) -> Option<Self> {
+        let count = outcomes.len();
+        Some(Measurement {
+            dataset: dataset.to_string(),
+            strategy,
+            puzzles: count,
+            solved,
+            steps: Summary::of(outcomes.iter().map(|outcome| outcome.steps))?,
+            steps_mean: outcomes.iter().map(|outcome| outcome.steps).sum::<u64>() as f64 / count as f64,
+            guesses_mean: outcomes.iter().map(|outcome| outcome.guesses).sum::<u64>() as f64 / count as f64,
+            time: Summary::of(outcomes.iter().map(|outcome| outcome.time))?,
+            time_mean: outcomes.iter().map(|outcome| outcome.time).sum::<Duration>() / count as u32,
+            wall,
+        })
+    }
+
+    /// Column names and values of the report, times in microseconds.
+    fn fields(&self) -> [(&'static str, String); 14] {
+        let (steps, guesses) = self.strategy.counters();
+        [
+            ("dataset", self.dataset.clone()),
+            ("strategy", self.strategy.name().to_string()),
+            ("steps_counter", steps.to_string()),
+            ("guesses_counter", guesses.to_string()),
+            ("puzzles", self.puzzles.to_string()),
+            ("solved", self.solved.to_string()),
+            ("steps_mean", format!("{:.1}", self.steps_mean)),
+            ("steps_median", self.steps.median.to_string()),
+            ("steps_max", self.steps.max.to_string()),
+            ("guesses_mean", format!("{:.1}", self.guesses_mean)),
+            ("time_mean_us", self.time_mean.as_micros().to_string()),
+            ("time_median_us", self.time.median.as_micros().to_string()),
+            ("time_max_us", self.time.max.as_micros().to_string()),
+            ("wall_us", self.wall.as_micros().to_string()),
+        ]
+    }
+}
+
+pub fn to_csv(measurements: &[Measurement]) -> String {
+    let Some(first) = measurements.first() else {
+        return String::new();
+    };
+    let header: Vec<&str> = first.fields().iter().map(|(name, _)| *name).collect();
+    let mut csv = header.join(",") + "\n";
+    for measurement in measurements {
+        let values: Vec<String> = measurement.fields().into_iter().map(|(_, value)| match value.contains([',', '"']) {
+            true => format!("\"{}\"", value.replace('"', "\"\"")),
+            false => value,
+        }).collect();
+        csv += &(values.join(",") + "\n");
+    }
+    csv
+}
+
+/// Array with an object per measurement. The dataset, strategy and counter names are
+/// strings, all other values numbers.
+pub fn to_json(measurements: &[Measurement]) -> String {
+    let objects: Vec<String> = measurements
+        .iter()
+        .map(|measurement| {
+            let fields: Vec<String> = measurement
+                .fields()
+                .into_iter()
+                .map(|(name, value)| match name {
+                    "dataset" | "strategy" | "steps_counter" | "guesses_counter" => format!("\"{}\": \"{}\"", name, value.replace('\\', "\\\\").replace('"', "\\\"")),
+                    _ => format!("\"{}\": {}", name, value),
+                })
+                .collect();
+            format!("  {{{}}}", fields.join(", "))
+        })
+        .collect();
+    format!("[\n{}\n]\n", objects.join(",\n"))
+}
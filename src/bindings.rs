@@ -0,0 +1,552 @@
+use std::collections::{HashMap, HashSet};
+
+use nannou::prelude::Key;
+
+use crate::config::parse_lines;
+use crate::storage;
+
+const BINDINGS_FILE: &str = "keys.conf";
+
+/// First line of the key binding files written since the defaults are commented out. Older
+/// files list every action and are migrated when loaded.
+const VERSION_LINE: &str = "# keys.conf version 2";
+
+/// Where an action can be used. Keys only need to be unique within a scope.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Scope {
+    Board,
+    Browser,
+    Calendar,
+}
+
+/// Something the player can do with a key.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Action {
+    CursorUp,
+    CursorDown,
+    CursorLeft,
+    CursorRight,
+    NextEmpty,
+    DigitFirst,
+    StartPause,
+    ChangeAlgorithm,
+    Race,
+    SolveInstantly,
+    ClearResult,
+    StepBack,
+    StepForward,
+    Faster,
+    Slower,
+    CellBreakpoint,
+    DepthBreakpoint,
+    BacktrackBreakpoint,
+    PauseTimer,
+    Hint,
+    Statistics,
+    Daily,
+    Easier,
+    Harder,
+    NewPuzzle,
+    Browse,
+    ClearSudoku,
+    Theme,
+    ShowAvailable,
+    HighlightRelevant,
+    ShapeCues,
+    FontSmaller,
+    FontLarger,
+    Heatmap,
+    Record,
+    RecordingFormat,
+    Export,
+    Fullscreen,
+    Quit,
+    BrowseUp,
+    BrowseDown,
+    BrowsePreviousPage,
+    BrowseNextPage,
+    NextCollection,
+    FilterClues,
+    FilterGuesses,
+    FilterSolved,
+    LoadPuzzle,
+    PreviousDay,
+    NextDay,
+    PreviousWeek,
+    NextWeek,
+    PreviousMonth,
+    NextMonth,
+    Today,
+    PlayDaily,
+}
+
+impl Action {
+    pub const ALL: [Action; 56] = [
+        Action::CursorUp,
+        Action::CursorDown,
+        Action::CursorLeft,
+        Action::CursorRight,
+        Action::NextEmpty,
+        Action::DigitFirst,
+        Action::StartPause,
+        Action::ChangeAlgorithm,
+        Action::Race,
+        Action::SolveInstantly,
+        Action::ClearResult,
+        Action::StepBack,
+        Action::StepForward,
+        Action::Faster,
+        Action::Slower,
+        Action::CellBreakpoint,
+        Action::DepthBreakpoint,
+        Action::BacktrackBreakpoint,
+        Action::PauseTimer,
+        Action::Hint,
+        Action::Statistics,
+        Action::Daily,
+        Action::Easier,
+        Action::Harder,
+        Action::NewPuzzle,
+        Action::Browse,
+        Action::ClearSudoku,
+        Action::Theme,
+        Action::ShowAvailable,
+        Action::HighlightRelevant,
+        Action::ShapeCues,
+        Action::FontSmaller,
+        Action::FontLarger,
+        Action::Heatmap,
+        Action::Record,
+        Action::RecordingFormat,
+        Action::Export,
+        Action::Fullscreen,
+        Action::Quit,
+        Action::BrowseUp,
+        Action::BrowseDown,
+        Action::BrowsePreviousPage,
+        Action::BrowseNextPage,
+        Action::NextCollection,
+        Action::FilterClues,
+        Action::FilterGuesses,
+        Action::FilterSolved,
+        Action::LoadPuzzle,
+        Action::PreviousDay,
+        Action::NextDay,
+        Action::PreviousWeek,
+        Action::NextWeek,
+        Action::PreviousMonth,
+        Action::NextMonth,
+        Action::Today,
+        Action::PlayDaily,
+    ];
+
+    /// Name of the action in the key binding file.
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::CursorUp => "cursor_up",
+            Action::CursorDown => "cursor_down",
+            Action::CursorLeft => "cursor_left",
+            Action::CursorRight => "cursor_right",
+            Action::NextEmpty => "next_empty",
+            Action::DigitFirst => "digit_first",
+            Action::StartPause => "start_pause",
+            Action::ChangeAlgorithm => "change_algorithm",
+            Action::Race => "race",
+            Action::SolveInstantly => "solve_instantly",
+            Action::ClearResult => "clear_result",
+            Action::StepBack => "step_back",
+            Action::StepForward => "step_forward",
+            Action::Faster => "faster",
+            Action::Slower => "slower",
+            Action::CellBreakpoint => "cell_breakpoint",
+            Action::DepthBreakpoint => "depth_breakpoint",
+            Action::BacktrackBreakpoint => "backtrack_breakpoint",
+            Action::PauseTimer => "pause_timer",
+            Action::Hint => "hint",
+            Action::Statistics => "statistics",
+            Action::Daily => "daily",
+            Action::Easier => "easier",
+            Action::Harder => "harder",
+            Action::NewPuzzle => "new_puzzle",
+            Action::Browse => "browse",
+            Action::ClearSudoku => "clear_sudoku",
+            Action::Theme => "theme",
+            Action::ShowAvailable => "show_available",
+            Action::HighlightRelevant => "highlight_relevant",
+            Action::ShapeCues => "shape_cues",
+            Action::FontSmaller => "font_smaller",
+            Action::FontLarger => "font_larger",
+            Action::Heatmap => "heatmap",
+            Action::Record => "record",
+            Action::RecordingFormat => "recording_format",
+            Action::Export => "export",
+            Action::Fullscreen => "fullscreen",
+            Action::Quit => "quit",
+            Action::BrowseUp => "browse_up",
+            Action::BrowseDown => "browse_down",
+            Action::BrowsePreviousPage => "browse_previous_page",
+            Action::BrowseNextPage => "browse_next_page",
+            Action::NextCollection => "next_collection",
+            Action::FilterClues => "filter_clues",
+            Action::FilterGuesses => "filter_guesses",
+            Action::FilterSolved => "filter_solved",
+            Action::LoadPuzzle => "load_puzzle",
+            Action::PreviousDay => "previous_day",
+            Action::NextDay => "next_day",
+            Action::PreviousWeek => "previous_week",
+            Action::NextWeek => "next_week",
+            Action::PreviousMonth => "previous_month",
+            Action::NextMonth => "next_month",
+            Action::Today => "today",
+            Action::PlayDaily => "play_daily",
+        }
+    }
+
+    pub fn scope(self) -> Scope {
+        match self {
+            Action::BrowseUp
+            | Action::BrowseDown
+            | Action::BrowsePreviousPage
+            | Action::BrowseNextPage
+            | Action::NextCollection
+            | Action::FilterClues
+            | Action::FilterGuesses
+            | Action::FilterSolved
+            | Action::LoadPuzzle => Scope::Browser,
+            Action::PreviousDay
+            | Action::NextDay
+            | Action::PreviousWeek
+            | Action::NextWeek
+            | Action::PreviousMonth
+            | Action::NextMonth
+            | Action::Today
+            | Action::PlayDaily => Scope::Calendar,
+            _ => Scope::Board,
+        }
+    }
+
+    /// Default keys before the cursor keys were added, if they were different.
+    fn legacy_default_keys(self) -> Option<&'static [Key]> {
+        match self {
+            Action::Faster => Some(&[Key::Up]),
+            Action::Slower => Some(&[Key::Down]),
+            Action::Easier => Some(&[Key::Left]),
+            Action::Harder => Some(&[Key::Right]),
+            Action::ClearSudoku => Some(&[Key::W]),
+            _ => None,
+        }
+    }
+
+    fn default_keys(self) -> &'static [Key] {
+        match self {
+            Action::CursorUp => &[Key::Up, Key::W, Key::K],
+            Action::CursorDown => &[Key::Down, Key::S, Key::J],
+            Action::CursorLeft => &[Key::Left, Key::A, Key::H],
+            Action::CursorRight => &[Key::Right, Key::D, Key::L],
+            Action::NextEmpty => &[Key::Tab],
+            Action::DigitFirst => &[Key::F],
+            Action::StartPause => &[Key::Space, Key::Return],
+            Action::ChangeAlgorithm => &[Key::Q],
+            Action::Race => &[Key::G],
+            Action::SolveInstantly => &[Key::I],
+            Action::ClearResult => &[Key::E],
+            Action::StepBack => &[Key::Comma],
+            Action::StepForward => &[Key::Period],
+            Action::Faster => &[Key::PageUp],
+            Action::Slower => &[Key::PageDown],
+            Action::CellBreakpoint => &[Key::B],
+            Action::DepthBreakpoint => &[Key::N],
+            Action::BacktrackBreakpoint => &[Key::M],
+            Action::PauseTimer => &[Key::P],
+            Action::Hint => &[Key::C],
+            Action::Statistics => &[Key::F2],
+            Action::Daily => &[Key::F3],
+            Action::Easier => &[Key::LBracket],
+            Action::Harder => &[Key::RBracket],
+            Action::NewPuzzle => &[Key::R],
+            Action::Browse => &[Key::O],
+            Action::ClearSudoku => &[Key::F5],
+            Action::Theme => &[Key::T],
+            Action::ShowAvailable => &[Key::Z],
+            Action::HighlightRelevant => &[Key::U],
+            Action::ShapeCues => &[Key::Y],
+            Action::FontSmaller => &[Key::Minus, Key::NumpadSubtract],
+            Action::FontLarger => &[Key::Equals, Key::Plus, Key::NumpadAdd],
+            Action::Heatmap => &[Key::V],
+            Action::Record => &[Key::F9],
+            Action::RecordingFormat => &[Key::F8],
+            Action::Export => &[Key::X],
+            Action::Fullscreen => &[Key::F11],
+            Action::Quit => &[Key::Escape],
+            Action::BrowseUp => &[Key::Up],
+            Action::BrowseDown => &[Key::Down],
+            Action::BrowsePreviousPage => &[Key::Left, Key::PageUp],
+            Action::BrowseNextPage => &[Key::Right, Key::PageDown],
+            Action::NextCollection => &[Key::Tab],
+            Action::FilterClues => &[Key::C],
+            Action::FilterGuesses => &[Key::R],
+            Action::FilterSolved => &[Key::F],
+            Action::LoadPuzzle => &[Key::Return],
+            Action::PreviousDay => &[Key::Left, Key::A, Key::H],
+            Action::NextDay => &[Key::Right, Key::D, Key::L],
+            Action::PreviousWeek => &[Key::Up, Key::W, Key::K],
+            Action::NextWeek => &[Key::Down, Key::S, Key::J],
+            Action::PreviousMonth => &[Key::PageUp],
+            Action::NextMonth => &[Key::PageDown],
+            Action::Today => &[Key::Home],
+            Action::PlayDaily => &[Key::Return],
+        }
+    }
+}
+
+/// Keys that can be bound, the digits and the erase keys stay fixed.
+const KEYS: [Key; 76] = [
+    Key::A, Key::B, Key::C, Key::D, Key::E, Key::F, Key::G, Key::H, Key::I, Key::J, Key::K, Key::L, Key::M,
+    Key::N, Key::O, Key::P, Key::Q, Key::R, Key::S, Key::T, Key::U, Key::V, Key::W, Key::X, Key::Y, Key::Z,
+    Key::F1, Key::F2, Key::F3, Key::F4, Key::F5, Key::F6, Key::F7, Key::F8, Key::F9, Key::F10, Key::F11, Key::F12,
+    Key::Escape, Key::Space, Key::Return, Key::Tab, Key::Insert, Key::Home, Key::End, Key::PageUp, Key::PageDown,
+    Key::Left, Key::Up, Key::Right, Key::Down,
+    Key::Apostrophe, Key::Backslash, Key::Comma, Key::Equals, Key::Grave, Key::LBracket, Key::Minus, Key::Period,
+    Key::RBracket, Key::Semicolon, Key::Slash, Key::Plus, Key::Asterisk, Key::Colon,
+    Key::NumpadAdd, Key::NumpadSubtract, Key::NumpadMultiply, Key::NumpadDivide, Key::NumpadDecimal,
+    Key::NumpadEnter, Key::NumpadEquals, Key::NumpadComma, Key::Pause, Key::Scroll, Key::Snapshot,
+];
+
+/// Name of a key in the key binding file, like `Comma` or `PageDown`.
+fn key_name(key: Key) -> String {
+    format!("{:?}", key)
+}
+
+/// Key as shown in the panel, punctuation as its symbol.
+fn key_label(key: Key) -> String {
+    let symbol = match key {
+        Key::Apostrophe => "'",
+        Key::Backslash => "\\",
+        Key::Comma => ",",
+        Key::Equals => "=",
+        Key::Grave => "`",
+        Key::LBracket => "[",
+        Key::Minus => "-",
+        Key::Period => ".",
+        Key::RBracket => "]",
+        Key::Semicolon => ";",
+        Key::Slash => "/",
+        Key::Plus => "+",
+        Key::Asterisk => "*",
+        Key::Colon => ":",
+        _ => return key_name(key),
+    };
+    symbol.to_string()
+}
+
+/// Key given by its name or its symbol, ignoring case.
+fn parse_key(name: &str) -> Option<Key> {
+    KEYS.into_iter()
+        .find(|key| key_name(*key).eq_ignore_ascii_case(name) || key_label(*key) == name)
+}
+
+/// Keys of every action, loaded from `keys.conf` in the config directory. Actions missing
+/// in the file keep their default keys, so changed defaults reach existing files.
+pub struct Bindings {
+    keys: HashMap<Action, Vec<Key>>,
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        Bindings {
+            keys: Action::ALL.into_iter().map(|action| (action, action.default_keys().to_vec())).collect(),
+        }
+    }
+}
+
+impl Bindings {
+    /// Loads the key bindings and writes a template with the defaults commented out if there
+    /// is no file yet. Problems are printed and the lines with them skipped.
+    pub fn load() -> Self {
+        let path = storage::config_dir().join(BINDINGS_FILE);
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            if let Err(error) = storage::write_config(BINDINGS_FILE, &Bindings::template()) {
+                eprintln!("could not write the key bindings: {}", error);
+            }
+            return Bindings::default();
+        };
+        let content = match content.lines().any(|line| line.trim() == VERSION_LINE) {
+            true => content,
+            false => {
+                let migrated = Bindings::migrate(&content);
+                let backup = format!("{}.bak", BINDINGS_FILE);
+                match storage::write_config(&backup, &content).and_then(|_| storage::write_config(BINDINGS_FILE, &migrated)) {
+                    Ok(()) => eprintln!("{}: updated to the new default keys, the old file is kept as {}", path.display(), backup),
+                    Err(error) => eprintln!("could not update the key bindings: {}", error),
+                }
+                migrated
+            }
+        };
+        let (bindings, problems) = Bindings::parse(&content);
+        for problem in problems {
+            eprintln!("{}: {}", path.display(), problem);
+        }
+        bindings
+    }
+
+    /// Parses lines like `hint = C, F1`. A key bound to several actions of the same scope
+    /// stays with the last one.
+    pub fn parse(content: &str) -> (Self, Vec<String>) {
+        let mut bindings = Bindings::default();
+        let problems = parse_lines(content, |name, keys| {
+            let action = Action::ALL
+                .into_iter()
+                .find(|action| action.name() == name)
+                .ok_or(format!("unknown action {}", name))?;
+            let names: Vec<&str> = keys.split(',').map(str::trim).filter(|key| !key.is_empty()).collect();
+            let parsed: Vec<Key> = names.iter().filter_map(|name| parse_key(name)).collect();
+            for (other, keys) in bindings.keys.iter_mut() {
+                if other.scope() == action.scope() {
+                    keys.retain(|key| !parsed.contains(key));
+                }
+            }
+            bindings.keys.insert(action, parsed);
+            match names.iter().find(|name| parse_key(name).is_none()) {
+                Some(unknown) => Err(format!("unknown key {}", unknown)),
+                None => Ok(()),
+            }
+        });
+        (bindings, problems)
+    }
+
+    /// Turns a file that lists every action into the template followed by the lines that
+    /// differ from the old and the current defaults.
+    fn migrate(content: &str) -> String {
+        let is_default = |line: &str| {
+            let Some((name, keys)) = line.split_once('=') else {
+                return false;
+            };
+            let Some(action) = Action::ALL.into_iter().find(|action| action.name() == name.trim()) else {
+                return false;
+            };
+            let keys: Option<Vec<Key>> = keys.split(',').map(str::trim).filter(|key| !key.is_empty()).map(parse_key).collect();
+            keys.is_some_and(|keys| keys == action.default_keys() || Some(keys.as_slice()) == action.legacy_default_keys())
+        };
+        let kept: Vec<&str> = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#') && !is_default(line))
+            .collect();
+        let mut migrated = Bindings::template();
+        if !kept.is_empty() {
+            migrated.push_str("\n# Changed keys from the previous version of this file:\n");
+            for line in kept {
+                migrated.push_str(line);
+                migrated.push('\n');
+            }
+        }
+        migrated
+    }
+
+    /// Content of a new key binding file, every action with its default keys commented out.
+    pub fn template() -> String {
+        let mut config = format!(
+            "{}\n\
+             # Key bindings, one action per line followed by its keys separated by commas.\n\
+             # Keys are named like A, F9, Space, Return, Comma, PageDown or NumpadAdd.\n\
+             # Remove the # in front of an action to change its keys, the others keep their defaults.\n",
+            VERSION_LINE
+        );
+        for action in Action::ALL {
+            let keys: Vec<String> = action.default_keys().iter().map(|key| key_name(*key)).collect();
+            config.push_str(&format!("# {} = {}\n", action.name(), keys.join(", ")));
+        }
+        config
+    }
+
+    pub fn keys(&self, action: Action) -> &[Key] {
+        &self.keys[&action]
+    }
+
+    /// Action of a pressed key among the actions of the scope.
+    pub fn action(&self, key: Key, scope: Scope) -> Option<Action> {
+        Action::ALL
+            .into_iter()
+            .find(|action| action.scope() == scope && self.keys(*action).contains(&key))
+    }
+
+    /// True if one of the keys of the action is held down.
+    pub fn is_down(&self, action: Action, down: &HashSet<Key>) -> bool {
+        self.keys(action).iter().any(|key| down.contains(key))
+    }
+
+    /// First key of the action in brackets, like `[Space]`.
+    pub fn label(&self, action: Action) -> String {
+        match self.keys(action).first() {
+            Some(key) => format!("[{}]", key_label(*key)),
+            None => "[-]".to_string(),
+        }
+    }
+
+    /// Help text of the panel, like `[,] [.] Step back / forward`.
+    pub fn hint(&self, actions: &[Action], text: &str) -> String {
+        let labels: Vec<String> = actions.iter().map(|action| self.label(*action)).collect();
+        format!("{} {}", labels.join(" "), text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn template_round_trip() {
+        let template = Bindings::template();
+        let (bindings, problems) = Bindings::parse(&template);
+        assert!(problems.is_empty(), "{:?}", problems);
+        let uncommented: String = template.lines().filter(|line| line.contains('=')).map(|line| format!("{}\n", &line[2..])).collect();
+        let (uncommented, problems) = Bindings::parse(&uncommented);
+        assert!(problems.is_empty(), "{:?}", problems);
+        for action in Action::ALL {
+            assert_eq!(bindings.keys(action), action.default_keys(), "{}", action.name());
+            assert_eq!(uncommented.keys(action), action.default_keys(), "{}", action.name());
+        }
+        assert_eq!(bindings.action(Key::Return, Scope::Board), Some(Action::StartPause));
+        assert_eq!(bindings.action(Key::Return, Scope::Calendar), Some(Action::PlayDaily));
+        assert_eq!(bindings.action(Key::K, Scope::Board), Some(Action::CursorUp));
+        assert_eq!(bindings.action(Key::C, Scope::Browser), Some(Action::FilterClues));
+        assert_eq!(bindings.hint(&[Action::StepBack, Action::StepForward], "Step"), "[,] [.] Step");
+    }
+
+    #[test]
+    fn rebinding_moves_keys() {
+        let (bindings, problems) = Bindings::parse("show_available = y, F1\n# comment\n\nhint = Z\nquit =\n");
+        assert!(problems.is_empty(), "{:?}", problems);
+        assert_eq!(bindings.keys(Action::ShowAvailable), &[Key::Y, Key::F1]);
+        assert_eq!(bindings.action(Key::Z, Scope::Board), Some(Action::Hint));
+        assert_eq!(bindings.keys(Action::Hint), &[Key::Z]);
+        assert_eq!(bindings.action(Key::C, Scope::Board), None);
+        assert_eq!(bindings.action(Key::C, Scope::Browser), Some(Action::FilterClues));
+        assert_eq!(bindings.label(Action::Quit), "[-]");
+        assert_eq!(bindings.keys(Action::Theme), &[Key::T]);
+    }
+
+    #[test]
+    fn migrates_files_listing_every_action() {
+        let old = "# Key bindings\nfaster = Up\nslower = Down\neasier = Left\nharder = Right\nclear_sudoku = W\nhint = F1\ntheme = T\n";
+        let migrated = Bindings::migrate(old);
+        assert!(migrated.lines().any(|line| line == VERSION_LINE));
+        assert!(migrated.ends_with("\nhint = F1\n"), "{}", migrated);
+        let (bindings, problems) = Bindings::parse(&migrated);
+        assert!(problems.is_empty(), "{:?}", problems);
+        assert_eq!(bindings.keys(Action::CursorUp), &[Key::Up, Key::W, Key::K]);
+        assert_eq!(bindings.keys(Action::Faster), &[Key::PageUp]);
+        assert_eq!(bindings.keys(Action::Easier), &[Key::LBracket]);
+        assert_eq!(bindings.keys(Action::ClearSudoku), &[Key::F5]);
+        assert_eq!(bindings.keys(Action::Hint), &[Key::F1]);
+        assert_eq!(Bindings::migrate(&Bindings::template()), Bindings::template());
+    }
+
+    #[test]
+    fn reports_problems() {
+        let (bindings, problems) = Bindings::parse("jump = J\nhint = Ctrl, H\nno equals sign\n");
+        assert_eq!(problems.len(), 3, "{:?}", problems);
+        assert!(problems[1].contains("Ctrl"));
+        assert_eq!(bindings.keys(Action::Hint), &[Key::H]);
+    }
+}
@@ -0,0 +1,118 @@
+use crate::sudoku::Tile;
+
+/// Bits 1 to 9 set, one for every digit.
+const ALL_DIGITS: u16 = 0b11_1111_1110;
+
+fn box_of(indx: usize) -> usize {
+    indx / 27 * 3 + indx % 9 / 3
+}
+
+/// Fast non-visual solver. Keeps the digits used in every row, column and box as
+/// bitmasks that are updated on every placement, so the candidates of a tile are a
+/// few bit operations away. Always branches on the tile with the fewest candidates.
+#[derive(Clone)]
+pub struct Bitboard {
+    cells: [u8; 81],
+    rows: [u16; 9],
+    columns: [u16; 9],
+    boxes: [u16; 9],
+    solution: Option<[u8; 81]>,
+    /// Number of digits placed during the search.
+    pub nodes: u64,
+    /// Number of placements made while more than one candidate was left.
+    pub guesses: u64,
+}
+
+impl Bitboard {
+    /// Creates the board from the filled tiles. Returns `None` if two of them conflict.
+    pub fn new(tiles: &[Tile; 81]) -> Option<Self> {
+        let mut board = Bitboard {
+            cells: [0; 81],
+            rows: [0; 9],
+            columns: [0; 9],
+            boxes: [0; 9],
+            solution: None,
+            nodes: 0,
+            guesses: 0,
+        };
+        for (indx, tile) in tiles.iter().enumerate() {
+            if let Some(n) = tile.to_u16() {
+                if board.used(indx) & 1 << n != 0 {
+                    return None;
+                }
+                board.place(indx, n as u8);
+            }
+        }
+        Some(board)
+    }
+
+    fn used(&self, indx: usize) -> u16 {
+        self.rows[indx / 9] | self.columns[indx % 9] | self.boxes[box_of(indx)]
+    }
+
+    fn place(&mut self, indx: usize, n: u8) {
+        self.cells[indx] = n;
+        self.rows[indx / 9] |= 1 << n;
+        self.columns[indx % 9] |= 1 << n;
+        self.boxes[box_of(indx)] |= 1 << n;
+    }
+
+    fn remove(&mut self, indx: usize) {
+        let mask = !(1 << self.cells[indx]);
+        self.cells[indx] = 0;
+        self.rows[indx / 9] &= mask;
+        self.columns[indx % 9] &= mask;
+        self.boxes[box_of(indx)] &= mask;
+    }
+
+    /// Solves the board and returns the digits of all tiles, or `None` if there is no solution.
+    pub fn solve(&mut self) -> Option<[u8; 81]> {
+        self.count_solutions(1);
+        self.solution
+    }
+
+    /// Counts the solutions of the board, stopping once `limit` solutions were found.
+    pub fn count_solutions(&mut self, limit: usize) -> usize {
+        let mut found = 0;
+        self.search(&mut found, limit);
+        found
+    }
+
+    /// Returns true once `limit` solutions were found and the search should stop.
+    fn search(&mut self, found: &mut usize, limit: usize) -> bool {
+        let mut best = None;
+        let mut best_count = u32::MAX;
+        for indx in (0..81).filter(|i| self.cells[*i] == 0) {
+            let count = (!self.used(indx) & ALL_DIGITS).count_ones();
+            if count < best_count {
+                best = Some(indx);
+                best_count = count;
+                if count <= 1 {
+                    break;
+                }
+            }
+        }
+        let Some(indx) = best else {
+            *found += 1;
+            if self.solution.is_none() {
+                self.solution = Some(self.cells);
+            }
+            return *found >= limit;
+        };
+
+        let mut candidates = !self.used(indx) & ALL_DIGITS;
+        while candidates != 0 {
+            let n = candidates.trailing_zeros() as u8;
+            candidates &= candidates - 1;
+            self.nodes += 1;
+            self.guesses += (best_count > 1) as u64;
+            self.place(indx, n);
+            let done = self.search(found, limit);
+            self.remove(indx);
+            if done {
+                return true;
+            }
+        }
+        false
+    }
+}
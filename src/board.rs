@@ -0,0 +1,223 @@
+use nannou::{color::{self, Rgba8}, prelude::*};
+
+use crate::sudoku::{Sudoku, Tile};
+use crate::theme::Theme;
+
+/// Primitive of the rendered board. Coordinates are in pixels with the board
+/// centered on the origin and y pointing up, like nannou's `Draw`.
+pub enum Shape {
+    Rect { center: Vec2, size: Vec2, z: f32, color: Rgba8 },
+    Line { start: Vec2, end: Vec2, weight: f32, z: f32, color: Rgba8 },
+    Text { center: Vec2, font_size: u32, text: String, z: f32, color: Rgba8 },
+}
+
+impl Shape {
+    pub fn z(&self) -> f32 {
+        match self {
+            Shape::Rect { z, .. } | Shape::Line { z, .. } | Shape::Text { z, .. } => *z,
+        }
+    }
+}
+
+/// Selects which parts of the board state are drawn.
+#[derive(Clone, Copy)]
+pub struct BoardOptions {
+    pub show_available: bool,
+    pub show_active: bool,
+    /// Marks tiles with shapes as well as colors: player digits are underlined, solver
+    /// digits outlined, conflicts hatched and the active tile framed.
+    pub shape_cues: bool,
+    /// Size of the digits relative to the default.
+    pub font_scale: f32,
+}
+
+pub fn opaque(color: rgb::Rgb<color::encoding::Srgb, u8>) -> Rgba8 {
+    with_alpha(color, 255)
+}
+
+pub fn with_alpha(color: rgb::Rgb<color::encoding::Srgb, u8>, alpha: u8) -> Rgba8 {
+    Rgba8 { color, alpha }
+}
+
+/// Center of the tile with the given index on a board of the given size.
+pub fn cell_center(indx: usize, size: f32) -> Vec2 {
+    vec2(
+        size / 9.0 * ((indx % 9) as f32 + 0.5) - size / 2.0,
+        size / 9.0 * ((indx / 9) as f32 + 0.5) - size / 2.0,
+    )
+}
+
+/// Lays out the grid and the numbers of the sudoku on a board of the given size.
+pub fn shapes(sudoku: &Sudoku, theme: &Theme, size: f32, options: BoardOptions) -> Vec<Shape> {
+    let mut shapes = grid(theme, size);
+    if let Some(active) = sudoku.active_cell().filter(|_| options.show_active && sudoku.is_active()) {
+        let center = cell_center(active, size);
+        shapes.push(Shape::Rect {
+            center,
+            size: vec2(size / 9.0, size / 9.0),
+            z: 1.0,
+            color: with_alpha(theme.active_color, theme.theme_alpha.saturating_mul(2)),
+        });
+        if options.shape_cues {
+            outline(center, size / 9.0 - 4.0, 4.0, opaque(theme.active_color), &mut shapes);
+        }
+    }
+    numbers(sudoku, theme, size, options, &mut shapes);
+    shapes
+}
+
+fn grid(theme: &Theme, size: f32) -> Vec<Shape> {
+    let mut shapes = vec![Shape::Rect {
+        center: vec2(0.0, 0.0),
+        size: vec2(size, size),
+        z: 0.0,
+        color: opaque(theme.tile_color),
+    }];
+    for i in 0..=9 {
+        let (color, z) = if i % 3 == 0 {
+            (theme.primary_color, 2.0)
+        } else {
+            (theme.secondary_color, 1.0)
+        };
+        let weight = if i % 3 == 0 { 2.0 } else { 1.0 };
+        let line = size / 9.0 * i as f32 - size / 2.0;
+        shapes.push(Shape::Line {
+            start: vec2(-size / 2.0, line),
+            end: vec2(size / 2.0, line),
+            weight,
+            z,
+            color: opaque(color),
+        });
+        shapes.push(Shape::Line {
+            start: vec2(line, -size / 2.0),
+            end: vec2(line, size / 2.0),
+            weight,
+            z,
+            color: opaque(color),
+        });
+    }
+    shapes
+}
+
+/// Square of four lines around `center`, drawn above the tiles and below the digits.
+fn outline(center: Vec2, width: f32, weight: f32, color: Rgba8, shapes: &mut Vec<Shape>) {
+    let half = width / 2.0;
+    let corners = [vec2(-half, -half), vec2(half, -half), vec2(half, half), vec2(-half, half)];
+    for i in 0..4 {
+        shapes.push(Shape::Line {
+            start: center + corners[i],
+            end: center + corners[(i + 1) % 4],
+            weight,
+            z: 3.0,
+            color,
+        });
+    }
+}
+
+fn numbers(sudoku: &Sudoku, theme: &Theme, size: f32, options: BoardOptions, shapes: &mut Vec<Shape>) {
+    // Digits stay inside their tile and candidates next to each other at any scale.
+    let digit_size = (size / 16.0 * options.font_scale).min(size / 10.0) as u32;
+    let candidate_size = (size / 40.0 * options.font_scale).min(size / 32.0) as u32;
+    let digit = |center: Vec2, n: u8, color| Shape::Text {
+        center,
+        font_size: digit_size,
+        text: n.to_string(),
+        z: 4.0,
+        color: opaque(color),
+    };
+    let conflicts = sudoku.conflicts();
+    for (i, t) in sudoku.tiles.iter().enumerate() {
+        let cell = cell_center(i, size);
+        if conflicts[i] {
+            shapes.push(Shape::Rect {
+                center: cell,
+                size: vec2(size / 9.0, size / 9.0),
+                z: 1.2,
+                color: with_alpha(theme.conflict_color, theme.theme_alpha.saturating_mul(2)),
+            });
+            if options.shape_cues {
+                // Stripes across the top right corner, clear of the digit.
+                let tile = size / 9.0;
+                for offset in [0.15, 0.3] {
+                    shapes.push(Shape::Line {
+                        start: cell + vec2(tile * (0.5 - offset), tile / 2.0),
+                        end: cell + vec2(tile / 2.0, tile * (0.5 - offset)),
+                        weight: 2.0,
+                        z: 3.0,
+                        color: opaque(theme.conflict_color),
+                    });
+                }
+            }
+        }
+        // Text is placed slightly below the cell center to compensate for the font baseline.
+        let text = vec2(cell.x, cell.y + size / 2.0 - size / 2.04);
+        match t {
+            Tile::Empty if options.show_available => {
+                let candidates = sudoku.candidates(i);
+                for n in 0..9 {
+                    if candidates >> n & 2 != 0 {
+                        shapes.push(Shape::Text {
+                            center: vec2(
+                                text.x + (n % 3 - 1) as f32 * size / 40.0,
+                                text.y + (n / 3 - 1) as f32 * size / 40.0 - size / 200.0,
+                            ),
+                            font_size: candidate_size,
+                            text: (n + 1).to_string(),
+                            z: 4.0,
+                            color: opaque(theme.secondary_color),
+                        });
+                    }
+                }
+            }
+            Tile::SolverVariable(n) => {
+                shapes.push(digit(text, *n, theme.solver_color));
+                if options.shape_cues {
+                    outline(cell, size / 9.0 * 0.7, 1.0, opaque(theme.solver_color), shapes);
+                }
+            }
+            Tile::Const(n) => {
+                shapes.push(Shape::Rect {
+                    center: cell,
+                    size: vec2(size / 9.0, size / 9.0),
+                    z: 1.1,
+                    color: with_alpha(theme.secondary_color, theme.theme_alpha),
+                });
+                shapes.push(digit(text, *n, theme.given_color));
+            }
+            Tile::PlayerVariable(n) => {
+                shapes.push(digit(text, *n, theme.player_color));
+                if options.shape_cues {
+                    let below = cell.y - size / 9.0 * 0.32;
+                    shapes.push(Shape::Line {
+                        start: vec2(cell.x - size / 9.0 * 0.25, below),
+                        end: vec2(cell.x + size / 9.0 * 0.25, below),
+                        weight: 2.0,
+                        z: 3.0,
+                        color: opaque(theme.player_color),
+                    });
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shape_cues_add_lines() {
+        let mut sudoku = Sudoku::default();
+        sudoku.tiles[0] = Tile::PlayerVariable(5);
+        sudoku.tiles[1] = Tile::SolverVariable(5);
+        let theme = Theme::builtin("Colorblind Light").unwrap();
+        let lines = |shape_cues| {
+            let options = BoardOptions { show_available: false, show_active: false, shape_cues, font_scale: 1.0 };
+            let shapes = shapes(&sudoku, &theme, 900.0, options);
+            shapes.iter().filter(|shape| matches!(shape, Shape::Line { .. })).count()
+        };
+        // An underline, an outline and two stripes on each of the conflicting tiles.
+        assert_eq!(lines(true), lines(false) + 1 + 4 + 2 * 2);
+    }
+}
@@ -0,0 +1,468 @@
+use std::borrow::Cow;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::bitboard::Bitboard;
+use crate::records::Records;
+use crate::storage;
+use crate::sudoku::{Difficulty, PuzzleId, Sudoku};
+
+/// Puzzles listed on a page of the browser.
+pub const PAGE: usize = 20;
+
+/// Directory below the data directory that holds the imported collections.
+const COLLECTIONS_DIRECTORY: &str = "collections";
+
+/// Time spent per frame on checking puzzles against the filters. Rating a puzzle means
+/// solving it, so scanning a whole collection can take a few seconds.
+const SCAN_BUDGET: Duration = Duration::from_millis(8);
+
+/// A list of puzzles, one of the bundled ones or a .sdm file imported by the player.
+pub struct Collection {
+    pub name: String,
+    /// Bundled collections have a difficulty, their puzzles have an ID.
+    pub difficulty: Option<Difficulty>,
+    puzzles: Vec<Cow<'static, str>>,
+    /// Guesses the bitboard solver needs for each puzzle, filled in when needed.
+    ratings: Vec<Option<u64>>,
+}
+
+impl Collection {
+    fn bundled(difficulty: Difficulty) -> Self {
+        Collection::new(difficulty.to_string().to_string(), Some(difficulty), difficulty.puzzles().lines().map(Cow::Borrowed).collect())
+    }
+
+    /// Reads a .sdm file, lines that are no puzzle are skipped.
+    fn read(path: &Path) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let puzzles: Vec<Cow<str>> = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| Sudoku::from_line(line).is_ok())
+            .map(|line| Cow::Owned(line.to_string()))
+            .collect();
+        if puzzles.is_empty() {
+            return Err(format!("{} contains no puzzles", path.display()));
+        }
+        let name = path.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+        Ok(Collection::new(name, None, puzzles))
+    }
+
+    fn new(name: String, difficulty: Option<Difficulty>, puzzles: Vec<Cow<'static, str>>) -> Self {
+        let ratings = vec![None; puzzles.len()];
+        Collection { name, difficulty, puzzles, ratings }
+    }
+
+    pub fn len(&self) -> usize {
+        self.puzzles.len()
+    }
+
+    pub fn puzzle(&self, indx: usize) -> &str {
+        &self.puzzles[indx]
+    }
+
+    /// Number of givens of a puzzle.
+    pub fn clues(&self, indx: usize) -> usize {
+        self.puzzles[indx].chars().filter(|c| *c != '0').count()
+    }
+
+    /// Guesses the bitboard solver needs, 0 if the puzzle can be solved by logic alone.
+    pub fn rating(&mut self, indx: usize) -> u64 {
+        if let Some(rating) = self.ratings[indx] {
+            return rating;
+        }
+        let sudoku = Sudoku::from_line(&self.puzzles[indx]).unwrap();
+        let rating = Bitboard::new(&sudoku.tiles).map_or(0, |mut board| {
+            board.solve();
+            board.guesses
+        });
+        self.ratings[indx] = Some(rating);
+        rating
+    }
+}
+
+/// Bundled collections followed by the imported ones.
+fn load_collections() -> Vec<Collection> {
+    let mut collections: Vec<Collection> = Difficulty::ALL.into_iter().map(Collection::bundled).collect();
+    let dir = storage::data_dir().join(COLLECTIONS_DIRECTORY);
+    let mut paths: Vec<_> = std::fs::read_dir(dir)
+        .map(|entries| entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()).collect())
+        .unwrap_or_default();
+    paths.sort();
+    collections.extend(paths.iter().filter(|path| path.extension().is_some_and(|ext| ext == "sdm")).filter_map(|path| Collection::read(path).ok()));
+    collections
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ClueFilter {
+    Any,
+    Few,
+    Medium,
+    Many,
+}
+
+impl ClueFilter {
+    pub fn next(self) -> Self {
+        match self {
+            ClueFilter::Any => ClueFilter::Few,
+            ClueFilter::Few => ClueFilter::Medium,
+            ClueFilter::Medium => ClueFilter::Many,
+            ClueFilter::Many => ClueFilter::Any,
+        }
+    }
+
+    pub fn to_string(self) -> &'static str {
+        match self {
+            ClueFilter::Any => "any",
+            ClueFilter::Few => "up to 25",
+            ClueFilter::Medium => "26 to 30",
+            ClueFilter::Many => "31 or more",
+        }
+    }
+
+    fn matches(self, clues: usize) -> bool {
+        match self {
+            ClueFilter::Any => true,
+            ClueFilter::Few => clues <= 25,
+            ClueFilter::Medium => (26..=30).contains(&clues),
+            ClueFilter::Many => clues >= 31,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum RatingFilter {
+    Any,
+    NoGuesses,
+    FewGuesses,
+    ManyGuesses,
+}
+
+impl RatingFilter {
+    pub fn next(self) -> Self {
+        match self {
+            RatingFilter::Any => RatingFilter::NoGuesses,
+            RatingFilter::NoGuesses => RatingFilter::FewGuesses,
+            RatingFilter::FewGuesses => RatingFilter::ManyGuesses,
+            RatingFilter::ManyGuesses => RatingFilter::Any,
+        }
+    }
+
+    pub fn to_string(self) -> &'static str {
+        match self {
+            RatingFilter::Any => "any",
+            RatingFilter::NoGuesses => "no guesses",
+            RatingFilter::FewGuesses => "1 to 10 guesses",
+            RatingFilter::ManyGuesses => "more than 10 guesses",
+        }
+    }
+
+    fn matches(self, rating: u64) -> bool {
+        match self {
+            RatingFilter::Any => true,
+            RatingFilter::NoGuesses => rating == 0,
+            RatingFilter::FewGuesses => (1..=10).contains(&rating),
+            RatingFilter::ManyGuesses => rating > 10,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum StatusFilter {
+    All,
+    Unsolved,
+    Solved,
+}
+
+impl StatusFilter {
+    pub fn next(self) -> Self {
+        match self {
+            StatusFilter::All => StatusFilter::Unsolved,
+            StatusFilter::Unsolved => StatusFilter::Solved,
+            StatusFilter::Solved => StatusFilter::All,
+        }
+    }
+
+    pub fn to_string(self) -> &'static str {
+        match self {
+            StatusFilter::All => "all",
+            StatusFilter::Unsolved => "unsolved",
+            StatusFilter::Solved => "solved",
+        }
+    }
+
+    fn matches(self, solved: bool) -> bool {
+        match self {
+            StatusFilter::All => true,
+            StatusFilter::Unsolved => !solved,
+            StatusFilter::Solved => solved,
+        }
+    }
+}
+
+/// A line of the list.
+pub struct Row {
+    /// Line in the collection, starting at 1.
+    pub line: usize,
+    pub clues: usize,
+    pub rating: u64,
+    pub solved: bool,
+}
+
+/// Pages through the puzzles of the collections that pass the filters. The filters are
+/// applied a few puzzles per frame, so pages show up before the whole collection is checked.
+pub struct Browser {
+    pub collections: Vec<Collection>,
+    pub collection: usize,
+    pub clues: ClueFilter,
+    pub rating: RatingFilter,
+    pub status: StatusFilter,
+    /// Line number typed by the player to jump to.
+    pub query: String,
+    pub page: usize,
+    /// Selected row of the page.
+    pub cursor: usize,
+    /// Rows of the current page, updated every frame.
+    pub rows: Vec<Row>,
+    /// Result of the last import or jump.
+    pub message: String,
+    /// Puzzles of the collection that pass the filters, as far as checked.
+    matches: Vec<usize>,
+    /// Number of puzzles checked against the filters.
+    scanned: usize,
+}
+
+impl Browser {
+    /// Opens the collection of the puzzle, on the page that lists it.
+    pub fn new(sudoku: &Sudoku) -> Self {
+        let mut browser = Browser {
+            collections: load_collections(),
+            collection: 0,
+            clues: ClueFilter::Any,
+            rating: RatingFilter::Any,
+            status: StatusFilter::All,
+            query: String::new(),
+            page: 0,
+            cursor: 0,
+            rows: Vec::new(),
+            message: String::new(),
+            matches: Vec::new(),
+            scanned: 0,
+        };
+        let difficulty = sudoku.id.map_or(sudoku.difficulty, |id| id.difficulty);
+        browser.collection = Difficulty::ALL.iter().position(|d| *d == difficulty).unwrap_or(0);
+        if let Some(id) = sudoku.id {
+            browser.go_to_line(id.line);
+        }
+        browser
+    }
+
+    pub fn current(&self) -> &Collection {
+        &self.collections[self.collection]
+    }
+
+    /// Starts over on the first page, after the collection or a filter changed.
+    pub fn restart(&mut self) {
+        self.matches.clear();
+        self.scanned = 0;
+        self.page = 0;
+        self.cursor = 0;
+    }
+
+    pub fn next_collection(&mut self) {
+        self.collection = (self.collection + 1) % self.collections.len();
+        self.restart();
+    }
+
+    /// Adds a .sdm file to the imported collections and opens it.
+    pub fn import(&mut self, path: &Path) -> Result<(), String> {
+        let collection = Collection::read(path)?;
+        let dir = storage::data_dir().join(COLLECTIONS_DIRECTORY);
+        std::fs::create_dir_all(&dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+        let target = dir.join(format!("{}.sdm", collection.name));
+        std::fs::copy(path, &target).map_err(|e| format!("{}: {}", target.display(), e))?;
+        self.message = format!("Imported {} puzzles as {}", collection.len(), collection.name);
+        self.collections = load_collections();
+        self.collection = self.collections.iter().rposition(|c| c.name == collection.name).unwrap_or(0);
+        self.restart();
+        Ok(())
+    }
+
+    /// Moves the cursor by a number of rows, across pages.
+    pub fn move_cursor(&mut self, rows: isize) {
+        let position = (self.page * PAGE + self.cursor).saturating_add_signed(rows);
+        let last = match self.is_scanned() {
+            true => self.matches.len().saturating_sub(1),
+            false => usize::MAX,
+        };
+        let position = position.min(last);
+        self.page = position / PAGE;
+        self.cursor = position % PAGE;
+    }
+
+    pub fn change_page(&mut self, pages: isize) {
+        self.move_cursor(pages * PAGE as isize);
+    }
+
+    /// Shows the puzzle in the given line of the collection. Clears the filters, so the
+    /// puzzle is listed.
+    pub fn go_to_line(&mut self, line: usize) {
+        if line == 0 || line > self.current().len() {
+            self.message = format!("{} has no line {}", self.current().name, line);
+            return;
+        }
+        self.clues = ClueFilter::Any;
+        self.rating = RatingFilter::Any;
+        self.status = StatusFilter::All;
+        self.restart();
+        self.page = (line - 1) / PAGE;
+        self.cursor = (line - 1) % PAGE;
+        self.message.clear();
+    }
+
+    /// Jumps to the line typed by the player.
+    pub fn submit_query(&mut self) {
+        if let Ok(line) = self.query.parse() {
+            self.go_to_line(line);
+        }
+        self.query.clear();
+    }
+
+    /// True once every puzzle of the collection has been checked against the filters.
+    pub fn is_scanned(&self) -> bool {
+        self.scanned == self.current().len()
+    }
+
+    /// Number of pages, known once the whole collection has been checked.
+    pub fn page_count(&self) -> Option<usize> {
+        self.is_scanned().then(|| self.matches.len().div_ceil(PAGE).max(1))
+    }
+
+    /// Checks further puzzles against the filters until the current page is full or the
+    /// time of this frame is used up, then updates the rows.
+    pub fn update(&mut self, records: &Records) {
+        let start = Instant::now();
+        let needed = (self.page + 1) * PAGE;
+        while self.matches.len() < needed && !self.is_scanned() && start.elapsed() < SCAN_BUDGET {
+            let indx = self.scanned;
+            if self.passes(indx, records) {
+                self.matches.push(indx);
+            }
+            self.scanned += 1;
+        }
+        // The cursor might point behind the last puzzle after a filter changed.
+        if self.is_scanned() && !self.matches.is_empty() {
+            self.move_cursor(0);
+        }
+        let first = (self.page * PAGE).min(self.matches.len());
+        let last = needed.min(self.matches.len());
+        let page: Vec<usize> = self.matches[first..last].to_vec();
+        self.rows = page
+            .into_iter()
+            .map(|indx| Row {
+                line: indx + 1,
+                clues: self.current().clues(indx),
+                rating: self.collections[self.collection].rating(indx),
+                solved: records.is_solved(self.current().puzzle(indx)),
+            })
+            .collect();
+    }
+
+    fn passes(&mut self, indx: usize, records: &Records) -> bool {
+        self.clues.matches(self.current().clues(indx))
+            && self.status.matches(records.is_solved(self.current().puzzle(indx)))
+            && (self.rating == RatingFilter::Any || self.rating.matches(self.collections[self.collection].rating(indx)))
+    }
+
+    /// Puzzle of the selected row and its ID, if it is one of the bundled puzzles.
+    pub fn selected(&self) -> Option<(&str, Option<PuzzleId>)> {
+        let row = self.rows.get(self.cursor)?;
+        let puzzle = self.current().puzzle(row.line - 1);
+        let id = self.current().difficulty.map(|difficulty| PuzzleId { difficulty, line: row.line });
+        Some((puzzle, id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Updates until the page is full or every puzzle has been checked.
+    fn fill_page(browser: &mut Browser, records: &Records) {
+        browser.update(records);
+        while browser.rows.len() < PAGE && !browser.is_scanned() {
+            browser.update(records);
+        }
+    }
+
+    fn hard_browser() -> Browser {
+        let mut sudoku = Sudoku::default();
+        sudoku.difficulty = Difficulty::Hard;
+        Browser::new(&sudoku)
+    }
+
+    #[test]
+    fn opens_on_current_puzzle() {
+        let mut sudoku = Sudoku::default();
+        let id: PuzzleId = "medium:1234".parse().unwrap();
+        sudoku.load_id(id);
+        let mut browser = Browser::new(&sudoku);
+        fill_page(&mut browser, &Records::default());
+        assert_eq!(browser.current().difficulty, Some(Difficulty::Medium));
+        assert_eq!(browser.selected(), Some((id.puzzle().unwrap(), Some(id))));
+    }
+
+    #[test]
+    fn cursor_moves_across_pages() {
+        let records = Records::default();
+        let mut browser = hard_browser();
+        browser.move_cursor(PAGE as isize + 3);
+        fill_page(&mut browser, &records);
+        assert_eq!((browser.page, browser.cursor), (1, 3));
+        assert_eq!(browser.rows[browser.cursor].line, PAGE + 4);
+        browser.move_cursor(-4);
+        assert_eq!((browser.page, browser.cursor), (0, PAGE - 1));
+        browser.change_page(-1);
+        assert_eq!((browser.page, browser.cursor), (0, 0));
+    }
+
+    #[test]
+    fn filters_apply_to_every_row() {
+        let records = Records::default();
+        let mut browser = hard_browser();
+        browser.clues = ClueFilter::Many;
+        browser.rating = RatingFilter::FewGuesses;
+        browser.restart();
+        browser.change_page(2);
+        fill_page(&mut browser, &records);
+        assert_eq!(browser.rows.len(), PAGE);
+        for row in browser.rows.iter() {
+            assert!(row.clues >= 31 && (1..=10).contains(&row.rating), "line {}", row.line);
+        }
+        assert!(browser.rows.windows(2).all(|rows| rows[0].line < rows[1].line));
+    }
+
+    #[test]
+    fn no_matches_scans_whole_collection() {
+        let mut browser = hard_browser();
+        browser.status = StatusFilter::Solved;
+        browser.restart();
+        fill_page(&mut browser, &Records::default());
+        assert!(browser.rows.is_empty());
+        assert_eq!(browser.page_count(), Some(1));
+    }
+
+    #[test]
+    fn go_to_line_clears_filters() {
+        let mut browser = hard_browser();
+        browser.clues = ClueFilter::Few;
+        browser.query = "45".to_string();
+        browser.submit_query();
+        fill_page(&mut browser, &Records::default());
+        assert!(browser.clues == ClueFilter::Any);
+        assert_eq!(browser.rows[browser.cursor].line, 45);
+        browser.query = "50001".to_string();
+        browser.submit_query();
+        assert_eq!(browser.message, "Hard has no line 50001");
+    }
+}
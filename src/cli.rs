@@ -0,0 +1,554 @@
+use std::collections::HashMap;
+use std::io::{ErrorKind, Write};
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Instant;
+
+use crate::batch::{self, Summary};
+use crate::config::Config;
+use crate::bench::{self, Measurement, Strategy};
+use crate::daily::Day;
+use crate::dlx::{self, House, SudokuCover};
+use crate::export;
+use crate::recorder::{self, HeadlessRecording};
+use crate::sat::{self, Cdcl, Cnf};
+use crate::sudoku::{Difficulty, Sudoku};
+use crate::theme::{Theme, Themes};
+use crate::validate;
+
+const USAGE: &str = "Usage:
+  nannou-sudoku                          Start the visualizer
+      --seed N   seed of the random puzzle selection and the stochastic solvers
+                 (default: random, shown in the panel)
+      --puzzle P puzzle to start with, 81 digits or a puzzle ID
+      --daily    start with the daily puzzle
+      --width PX, --height PX  size of the window
+      --theme T  dark, light, discord, high contrast, colorblind dark,
+                 colorblind light or a theme of the themes directory
+      --difficulty D  easy, medium, hard or toughest
+      --steps-per-frame F  speed of the animated solvers
+      --available true|false  show the available numbers of empty tiles
+      --highlight true|false  highlight the tiles related to the selected one
+      --shape-cues true|false  mark player and solver digits, conflicts and the
+                 active tile with shapes as well as colors
+      --font-scale F  size of the text, 0.75 to 1.5 (default: 1)
+                 These options replace the settings of config.toml for the session.
+  nannou-sudoku daily                    Print the ID and digits of the daily puzzle
+      --date D   day of the puzzle like 2024-03-31 (default: today, in UTC)
+  nannou-sudoku theme [NAME]             Print a theme as a theme file, to start a new one
+      <NAME>     a built-in theme like dark or high contrast, or a theme file name (default: dark)
+                 Theme files are read from the themes directory of the config
+                 directory and reloaded when they change.
+  nannou-sudoku record <PUZZLE> <OUTPUT> Render a solver run without a window
+      <PUZZLE>   81 digits, 0 for empty tiles, or a puzzle ID
+      <OUTPUT>   file ending in .gif or a directory for a PNG sequence
+      --every N  solver steps per frame (default: about 300 frames in total)
+      --fps F    frames per second of the GIF (default: 30)
+      --size PX  width and height of the frames (default: 600)
+      --theme T  a built-in theme like dark or high contrast, or a theme file name (default: dark)
+      --available  draw the available numbers of empty tiles
+      --shape-cues  mark the digits of the solver and the active tile with shapes
+  nannou-sudoku booklet <FILE> <OUTPUT>  Print puzzles of a .sdm file into a PDF
+      <FILE>     .sdm file with one puzzle per line
+      <OUTPUT>   PDF file to write
+      --start N  line of the first puzzle, starting at 1 (default: 1)
+      --count N  number of puzzles (default: 12)
+      --per-page N  puzzles per A4 page, 1, 2, 4 or 6 (default: 6)
+      --theme T  a built-in theme like dark or high contrast, or a theme file name (default: light)
+      --notes    print the available numbers of empty tiles
+  nannou-sudoku solve <PUZZLES>          Solve puzzles with the fast bitboard solver
+      <PUZZLES>  81 digits, a puzzle ID or a .sdm file with one puzzle per line
+      --quiet    only print the summary, not the solutions
+      --threads N  number of threads (default: one per core)
+  nannou-sudoku count <PUZZLE>           Enumerate all solutions with dancing links
+      <PUZZLE>   81 digits, 0 for empty tiles, or a puzzle ID
+      --limit N  stop after N solutions (default: 1000)
+      --print    print every solution
+      --diagonal both main diagonals must contain every digit once
+      --regions R  81 digits 0 to 8, the jigsaw region of every tile instead of the boxes
+  nannou-sudoku cnf <PUZZLE>             Print the puzzle as a DIMACS CNF formula
+      <PUZZLE>   81 digits, 0 for empty tiles, or a puzzle ID
+      --diagonal, --regions R  variant rules as for count
+  nannou-sudoku sat <INPUT>              Solve with the built-in CDCL SAT solver
+      <INPUT>    a .cnf file in DIMACS format, the model is printed like SAT solvers do,
+                 or 81 digits, a puzzle ID or a .sdm file, which are encoded, solved,
+                 decoded and checked
+      --quiet    only print the summary, not the solutions
+      --diagonal, --regions R  variant rules as for count
+  nannou-sudoku decode <MODEL>           Print the sudoku of a model found by a SAT solver
+      <MODEL>    output of the solver for a formula written by cnf, - for stdin
+  nannou-sudoku bench [FILES...]         Benchmark the solvers on puzzle collections
+      [FILES]    .sdm files (default: the four collections in input/)
+      --sample N puzzles per file, spread evenly over it (default: 50, 0 for all)
+      --strategies S  comma separated list of bitboard, sat, backtracking, dlx, annealing
+                 and genetic (default: all)
+      --seed N   seed of the stochastic solvers (default: 0)
+      --max-steps N  steps after which the animated solvers give up (default: depends on
+                 the solver, about a second per puzzle)
+      --threads N  number of threads (default: one per core)
+      --csv FILE, --json FILE  write the report to a file
+  nannou-sudoku help                     Show this message
+
+A puzzle ID names a line of the bundled collections easy, medium, hard and toughest,
+e.g. hard:1234 for the puzzle in line 1234 of input/Sudoku_hard.sdm. The panel shows
+the ID of the current puzzle.";
+
+/// Positional arguments and `--name value` options of a command.
+struct Args {
+    positional: Vec<String>,
+    options: HashMap<String, String>,
+}
+
+impl Args {
+    /// Options listed in `flags` take no value.
+    fn parse(args: &[String], flags: &[&str]) -> Result<Self, String> {
+        let mut positional = Vec::new();
+        let mut options = HashMap::new();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.strip_prefix("--") {
+                Some(name) if flags.contains(&name) => _ = options.insert(name.to_string(), String::new()),
+                Some(name) => {
+                    let value = args.next().ok_or(format!("missing value for --{}", name))?;
+                    options.insert(name.to_string(), value.clone());
+                }
+                None => positional.push(arg.clone()),
+            }
+        }
+        Ok(Args { positional, options })
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.options.contains_key(name)
+    }
+
+    fn option<T: FromStr>(&self, name: &str) -> Result<Option<T>, String> {
+        self.options
+            .get(name)
+            .map(|value| value.parse().map_err(|_| format!("invalid value for --{}: {}", name, value)))
+            .transpose()
+    }
+
+    fn positional(&self, index: usize, name: &str) -> Result<&str, String> {
+        self.positional
+            .get(index)
+            .map(String::as_str)
+            .ok_or(format!("missing argument <{}>\n\n{}", name, USAGE))
+    }
+
+    /// Theme given by `--theme`, a built-in theme or one of the themes directory.
+    fn theme(&self, default: &str) -> Result<Theme, String> {
+        Themes::load().find(self.options.get("theme").map_or(default, String::as_str))
+    }
+}
+
+/// Options of the visualizer, given without a command.
+pub struct Launch {
+    pub seed: Option<u64>,
+    pub puzzle: Option<Sudoku>,
+    pub daily: bool,
+    width: Option<u32>,
+    height: Option<u32>,
+    theme: Option<String>,
+    difficulty: Option<Difficulty>,
+    steps_per_frame: Option<f32>,
+    show_available: Option<bool>,
+    highlight_relevant: Option<bool>,
+    shape_cues: Option<bool>,
+    font_scale: Option<f32>,
+}
+
+impl Launch {
+    /// The settings of the config file with the options given on the command line.
+    pub fn settings(&self, config: &Config) -> Config {
+        Config {
+            width: self.width.unwrap_or(config.width),
+            height: self.height.unwrap_or(config.height),
+            theme: self.theme.clone().unwrap_or_else(|| config.theme.clone()),
+            difficulty: self.difficulty.unwrap_or(config.difficulty),
+            steps_per_frame: self.steps_per_frame.unwrap_or(config.steps_per_frame),
+            show_available: self.show_available.unwrap_or(config.show_available),
+            highlight_relevant: self.highlight_relevant.unwrap_or(config.highlight_relevant),
+            shape_cues: self.shape_cues.unwrap_or(config.shape_cues),
+            font_scale: self.font_scale.unwrap_or(config.font_scale),
+            digit_first: config.digit_first,
+            seed: self.seed.or(config.seed),
+        }
+    }
+}
+
+/// True if the arguments start the visualizer instead of running a command.
+pub fn is_launch(args: &[String]) -> bool {
+    args.first().is_none_or(|arg| arg.starts_with("--") && arg != "--help")
+}
+
+pub fn launch(args: &[String]) -> Result<Launch, String> {
+    let args = Args::parse(args, &["daily"])?;
+    if let Some(arg) = args.positional.first() {
+        return Err(format!("unexpected argument: {}\n\n{}", arg, USAGE));
+    }
+    Ok(Launch {
+        seed: args.option("seed")?,
+        puzzle: args.options.get("puzzle").map(|puzzle| parse_puzzle(puzzle)).transpose()?,
+        daily: args.flag("daily"),
+        width: args.option("width")?,
+        height: args.option("height")?,
+        theme: args.options.get("theme").map(|name| Themes::load().find(name).map(|theme| theme.name)).transpose()?,
+        difficulty: args
+            .options
+            .get("difficulty")
+            .map(|name| Difficulty::from_name(name).ok_or(format!("unknown difficulty {}, use easy, medium, hard or toughest", name)))
+            .transpose()?,
+        steps_per_frame: args.option("steps-per-frame")?,
+        show_available: args.option("available")?,
+        highlight_relevant: args.option("highlight")?,
+        shape_cues: args.option("shape-cues")?,
+        font_scale: args.option("font-scale")?,
+    })
+}
+
+/// Runs the command given on the command line.
+pub fn run(args: &[String]) -> Result<(), String> {
+    match args[0].as_str() {
+        "record" => record(&Args::parse(&args[1..], &["available", "shape-cues"])?),
+        "solve" => solve(&Args::parse(&args[1..], &["quiet"])?),
+        "count" => count(&Args::parse(&args[1..], &["print", "diagonal"])?),
+        "cnf" => cnf(&Args::parse(&args[1..], &["diagonal"])?),
+        "sat" => solve_sat(&Args::parse(&args[1..], &["quiet", "diagonal"])?),
+        "decode" => decode(&Args::parse(&args[1..], &[])?),
+        "booklet" => booklet(&Args::parse(&args[1..], &["notes"])?),
+        "bench" => benchmark(&Args::parse(&args[1..], &[])?),
+        "daily" => daily(&Args::parse(&args[1..], &[])?),
+        "theme" => print_theme(&Args::parse(&args[1..], &[])?),
+        "help" | "--help" | "-h" => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        command => Err(format!("unknown command: {}\n\n{}", command, USAGE)),
+    }
+}
+
+/// Puzzle given as 81 digits or as a puzzle ID like `hard:1234`.
+fn parse_puzzle(source: &str) -> Result<Sudoku, String> {
+    if !source.contains(':') {
+        return Sudoku::from_line(source);
+    }
+    let mut sudoku = Sudoku::default();
+    sudoku.load_id(source.parse()?);
+    Ok(sudoku)
+}
+
+fn record(args: &Args) -> Result<(), String> {
+    let sudoku = parse_puzzle(args.positional(0, "PUZZLE")?)?;
+    let output = Path::new(args.positional(1, "OUTPUT")?);
+    let settings = HeadlessRecording {
+        size: args.option("size")?.unwrap_or(600),
+        fps: args.option("fps")?.unwrap_or(30.0),
+        every: args.option("every")?,
+        show_available: args.flag("available"),
+        shape_cues: args.flag("shape-cues"),
+    };
+    let frames = recorder::record_headless(sudoku, &args.theme("Dark")?, output, &settings)?;
+    println!("Wrote {} frames to {}", frames, output.display());
+    Ok(())
+}
+
+fn booklet(args: &Args) -> Result<(), String> {
+    let file = Path::new(args.positional(0, "FILE")?);
+    let output = Path::new(args.positional(1, "OUTPUT")?);
+    let start: usize = args.option("start")?.unwrap_or(1);
+    let count: usize = args.option("count")?.unwrap_or(12);
+    let per_page: usize = args.option("per-page")?.unwrap_or(6);
+    let content = std::fs::read_to_string(file).map_err(|e| format!("{}: {}", file.display(), e))?;
+    let name = file.file_stem().map(|stem| stem.to_string_lossy()).unwrap_or_default();
+    let puzzles = content
+        .lines()
+        .enumerate()
+        .skip(start.saturating_sub(1))
+        .take(count)
+        .map(|(i, line)| Ok((format!("{} #{}", name, i + 1), Sudoku::from_line(line.trim())?)))
+        .collect::<Result<Vec<_>, String>>()?;
+    if puzzles.is_empty() {
+        return Err(format!("no puzzles in {} from line {}", file.display(), start));
+    }
+    let pdf = export::pdf(&puzzles, &args.theme("Light")?, args.flag("notes"), per_page);
+    std::fs::write(output, pdf).map_err(|e| format!("{}: {}", output.display(), e))?;
+    println!("Wrote {} puzzles to {}", puzzles.len(), output.display());
+    Ok(())
+}
+
+/// Reads the puzzles of a .sdm file, or the single puzzle given as 81 digits or puzzle ID.
+/// Fails if there are no puzzles.
+fn read_puzzles(source: &str) -> Result<Vec<Sudoku>, String> {
+    let path = Path::new(source);
+    if !path.exists() {
+        if path.extension().is_some() || source.contains(std::path::MAIN_SEPARATOR) {
+            return Err(format!("{}: no such file", source));
+        }
+        return parse_puzzle(source).map(|sudoku| vec![sudoku]);
+    }
+    let content = std::fs::read_to_string(source).map_err(|e| format!("{}: {}", source, e))?;
+    let puzzles = content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| Sudoku::from_line(line.trim()))
+        .collect::<Result<Vec<_>, String>>()?;
+    match puzzles.is_empty() {
+        true => Err(format!("no puzzles in {}", source)),
+        false => Ok(puzzles),
+    }
+}
+
+fn solve(args: &Args) -> Result<(), String> {
+    let puzzles = read_puzzles(args.positional(0, "PUZZLES")?)?;
+    let threads = batch::thread_count(args.option("threads")?.unwrap_or_else(batch::default_threads), puzzles.len());
+    let start = Instant::now();
+    let outcomes = batch::run(&puzzles, threads, batch::solve_bitboard, |finished| {
+        if puzzles.len() > 1 {
+            eprint!("\rSolving {} / {}", finished, puzzles.len());
+        }
+    });
+    let elapsed = start.elapsed();
+    if puzzles.len() > 1 {
+        eprintln!();
+    }
+    if !args.flag("quiet") {
+        for outcome in outcomes.iter() {
+            match outcome.solution {
+                Some(solution) => println!("{}", digits_to_line(&solution)),
+                None => println!("no solution"),
+            }
+        }
+    }
+    let solved = outcomes.iter().filter(|outcome| outcome.solution.is_some()).count();
+    let guesses: u64 = outcomes.iter().map(|outcome| outcome.guesses).sum();
+    eprintln!(
+        "Solved {} of {} puzzles in {:.3}s on {} threads ({:.1} guesses per puzzle)",
+        solved,
+        puzzles.len(),
+        elapsed.as_secs_f64(),
+        threads,
+        guesses as f64 / puzzles.len() as f64,
+    );
+    if let Some(steps) = Summary::of(outcomes.iter().map(|outcome| outcome.steps)) {
+        eprintln!("Steps per puzzle: min {}, median {}, max {}", steps.min, steps.median, steps.max);
+    }
+    if let Some(time) = Summary::of(outcomes.iter().map(|outcome| outcome.time)) {
+        eprintln!("Time per puzzle: min {:.1?}, median {:.1?}, max {:.1?}", time.min, time.median, time.max);
+    }
+    Ok(())
+}
+
+fn benchmark(args: &Args) -> Result<(), String> {
+    let files: Vec<&str> = match args.positional.is_empty() {
+        true => bench::DATASETS.to_vec(),
+        false => args.positional.iter().map(String::as_str).collect(),
+    };
+    let strategies = match args.options.get("strategies") {
+        Some(names) => names
+            .split(',')
+            .map(|name| Strategy::from_name(name.trim()).ok_or(format!("unknown strategy: {}", name)))
+            .collect::<Result<Vec<_>, String>>()?,
+        None => Strategy::ALL.to_vec(),
+    };
+    let sample: usize = args.option("sample")?.unwrap_or(50);
+    let max_steps: Option<u64> = args.option("max-steps")?;
+    let threads = args.option("threads")?.unwrap_or_else(batch::default_threads);
+    let seed: u64 = args.option("seed")?.unwrap_or(0);
+    let houses = dlx::standard_houses();
+
+    let mut measurements = Vec::new();
+    println!(
+        "{:<20} {:<13} {:>11} {:>12} {:>12} {:>12} {:>11} {:>11}  Steps / guesses count",
+        "Dataset", "Strategy", "Solved", "Steps (med)", "Steps (max)", "Guesses", "Time (med)", "Time (max)"
+    );
+    for file in files {
+        let puzzles = read_puzzles(file)?;
+        let mut puzzles = match sample {
+            0 => puzzles,
+            n => bench::sample(&puzzles, n),
+        };
+        puzzles.iter_mut().for_each(|sudoku| sudoku.reseed(seed));
+        let dataset = Path::new(file).file_stem().map_or(file.into(), |stem| stem.to_string_lossy());
+        for strategy in strategies.iter() {
+            let max_steps = max_steps.unwrap_or(strategy.default_max_steps());
+            let start = Instant::now();
+            let outcomes = batch::run(&puzzles, threads, |sudoku| strategy.solve(sudoku, max_steps), |finished| {
+                eprint!("\r{} {} {} / {}", dataset, strategy.name(), finished, puzzles.len());
+            });
+            let wall = start.elapsed();
+            eprint!("\r\x1b[K");
+            let solved = puzzles
+                .iter()
+                .zip(outcomes.iter())
+                .filter(|(sudoku, outcome)| outcome.solution.is_some_and(|digits| validate::solution(&sudoku.tiles, &digits, &houses).is_ok()))
+                .count();
+            let Some(measurement) = Measurement::new(&dataset, *strategy, &outcomes, solved, wall) else {
+                continue;
+            };
+            let (steps, guesses) = strategy.counters();
+            println!(
+                "{:<20} {:<13} {:>11} {:>12} {:>12} {:>12.1} {:>11.1?} {:>11.1?}  {} / {}",
+                measurement.dataset,
+                strategy.name(),
+                format!("{} / {}", solved, measurement.puzzles),
+                measurement.steps.median,
+                measurement.steps.max,
+                measurement.guesses_mean,
+                measurement.time.median,
+                measurement.time.max,
+                steps,
+                guesses,
+            );
+            measurements.push(measurement);
+        }
+    }
+    if let Some(path) = args.options.get("csv") {
+        std::fs::write(path, bench::to_csv(&measurements)).map_err(|e| format!("{}: {}", path, e))?;
+    }
+    if let Some(path) = args.options.get("json") {
+        std::fs::write(path, bench::to_json(&measurements)).map_err(|e| format!("{}: {}", path, e))?;
+    }
+    Ok(())
+}
+
+fn daily(args: &Args) -> Result<(), String> {
+    let day = match args.options.get("date") {
+        Some(date) => date.parse()?,
+        None => Day::today(),
+    };
+    let id = day.puzzle();
+    println!("{} {}", day, id);
+    println!("{}", id.puzzle().expect("daily puzzles are lines of the collections"));
+    Ok(())
+}
+
+fn print_theme(args: &Args) -> Result<(), String> {
+    let name = args.positional.first().map_or("Dark", String::as_str);
+    print!("{}", Themes::load().find(name)?.to_toml());
+    Ok(())
+}
+
+fn count(args: &Args) -> Result<(), String> {
+    let sudoku = parse_puzzle(args.positional(0, "PUZZLE")?)?;
+    let limit: usize = args.option("limit")?.unwrap_or(1000).max(1);
+    let mut cover = SudokuCover::new(&sudoku.tiles, &houses(args)?);
+    // One solution more than the limit tells whether there are more than shown.
+    let mut remaining = limit.saturating_add(1);
+    let mut out = std::io::stdout().lock();
+    let mut written = Ok(());
+    let found = cover.dlx.solve_all(|rows| {
+        remaining -= 1;
+        if args.flag("print") && remaining > 0 {
+            written = writeln!(out, "{}", digits_to_line(&SudokuCover::solution(rows)));
+        }
+        remaining > 0 && written.is_ok()
+    });
+    let written = written.and_then(|_| match found > limit {
+        true => writeln!(out, "more than {} solutions (limit reached)", limit),
+        false => writeln!(out, "{} solution{}", found, if found == 1 { "" } else { "s" }),
+    });
+    output_result(written)
+}
+
+/// Result of writing to stdout. A reader that stops early, like `| head`, closes the pipe,
+/// which ends the output without an error.
+fn output_result(result: std::io::Result<()>) -> Result<(), String> {
+    match result {
+        Err(error) if error.kind() != ErrorKind::BrokenPipe => Err(error.to_string()),
+        _ => Ok(()),
+    }
+}
+
+fn digits_to_line(digits: &[u8; 81]) -> String {
+    digits.iter().map(|n| n.to_string()).collect()
+}
+
+/// Houses of the standard rules or of the variant given by `--regions` and `--diagonal`.
+fn houses(args: &Args) -> Result<Vec<House>, String> {
+    let mut houses = match args.options.get("regions") {
+        Some(regions) => {
+            let regions: [u8; 81] = regions
+                .chars()
+                .map(|c| c.to_digit(9).map(|d| d as u8))
+                .collect::<Option<Vec<u8>>>()
+                .and_then(|regions| regions.try_into().ok())
+                .ok_or("--regions must consist of 81 digits from 0 to 8")?;
+            dlx::jigsaw_houses(&regions).ok_or("every region must have nine tiles")?
+        }
+        None => dlx::standard_houses(),
+    };
+    if args.flag("diagonal") {
+        houses.extend(dlx::diagonal_houses());
+    }
+    Ok(houses)
+}
+
+fn cnf(args: &Args) -> Result<(), String> {
+    let sudoku = parse_puzzle(args.positional(0, "PUZZLE")?)?;
+    let dimacs = sat::encode(&sudoku.tiles, &houses(args)?).to_dimacs();
+    output_result(std::io::stdout().lock().write_all(dimacs.as_bytes()))
+}
+
+fn solve_sat(args: &Args) -> Result<(), String> {
+    let input = args.positional(0, "INPUT")?;
+    if input.ends_with(".cnf") {
+        let dimacs = std::fs::read_to_string(input).map_err(|e| format!("{}: {}", input, e))?;
+        let cnf = Cnf::from_dimacs(&dimacs)?;
+        let mut solver = Cdcl::new(&cnf);
+        let mut out = std::io::stdout().lock();
+        let written = match solver.solve() {
+            Some(model) => writeln!(out, "s SATISFIABLE\nv {} 0", model.iter().map(|l| l.to_string()).collect::<Vec<_>>().join(" ")),
+            None => writeln!(out, "s UNSATISFIABLE"),
+        };
+        eprintln!("c {} conflicts, {} decisions", solver.conflicts, solver.decisions);
+        return output_result(written);
+    }
+
+    let puzzles = read_puzzles(input)?;
+    let houses = houses(args)?;
+    let start = Instant::now();
+    let mut solved = 0;
+    let mut conflicts = 0;
+    for sudoku in puzzles.iter() {
+        // Goes through the DIMACS text, like an external solver would.
+        let cnf = Cnf::from_dimacs(&sat::encode(&sudoku.tiles, &houses).to_dimacs())?;
+        let mut solver = Cdcl::new(&cnf);
+        let solution = solver.solve().map(|model| sat::decode(&model));
+        conflicts += solver.conflicts;
+        if let Some(digits) = solution {
+            validate::solution(&sudoku.tiles, &digits, &houses)
+                .map_err(|e| format!("invalid solution {}: {}", digits_to_line(&digits), e))?;
+            solved += 1;
+        }
+        if !args.flag("quiet") {
+            match solution {
+                Some(digits) => println!("{}", digits_to_line(&digits)),
+                None => println!("no solution"),
+            }
+        }
+    }
+    let elapsed = start.elapsed();
+    eprintln!(
+        "Solved {} of {} puzzles in {:.3}s ({:.1} ms and {:.1} conflicts per puzzle)",
+        solved,
+        puzzles.len(),
+        elapsed.as_secs_f64(),
+        elapsed.as_secs_f64() * 1e3 / puzzles.len() as f64,
+        conflicts as f64 / puzzles.len() as f64,
+    );
+    Ok(())
+}
+
+fn decode(args: &Args) -> Result<(), String> {
+    let source = args.positional(0, "MODEL")?;
+    let output = match source {
+        "-" => std::io::read_to_string(std::io::stdin()).map_err(|e| e.to_string())?,
+        _ => std::fs::read_to_string(source).map_err(|e| format!("{}: {}", source, e))?,
+    };
+    let digits = sat::decode(&sat::parse_model(&output)?);
+    if digits.contains(&0) {
+        return Err("the model does not assign a digit to every tile".to_string());
+    }
+    output_result(writeln!(std::io::stdout().lock(), "{}", digits_to_line(&digits)))
+}
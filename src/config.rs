@@ -0,0 +1,307 @@
+use std::str::FromStr;
+
+use crate::storage;
+use crate::sudoku::Difficulty;
+
+const CONFIG_FILE: &str = "config.toml";
+
+/// Settings kept between sessions in `config.toml` in the config directory. The file is
+/// written when a setting changes in the visualizer.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Config {
+    /// Size of the window in logical pixels.
+    pub width: u32,
+    pub height: u32,
+    /// Name of the color theme.
+    pub theme: String,
+    /// Difficulty of the random puzzles.
+    pub difficulty: Difficulty,
+    pub steps_per_frame: f32,
+    pub show_available: bool,
+    pub highlight_relevant: bool,
+    pub shape_cues: bool,
+    pub font_scale: f32,
+    /// Pick a digit on the digit pad first, then the tiles to put it in.
+    pub digit_first: bool,
+    /// Seed of every session, a random one if `None`. Only set by editing the file.
+    pub seed: Option<u64>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            width: 1000,
+            height: 800,
+            theme: "Dark".to_string(),
+            difficulty: Difficulty::Medium,
+            steps_per_frame: 1.0,
+            show_available: true,
+            highlight_relevant: true,
+            shape_cues: false,
+            font_scale: 1.0,
+            digit_first: false,
+            seed: None,
+        }
+    }
+}
+
+impl Config {
+    /// Loads the settings, the defaults if there is no file yet. Problems are printed and
+    /// the lines with them skipped.
+    pub fn load() -> Self {
+        let path = storage::config_dir().join(CONFIG_FILE);
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            return Config::default();
+        };
+        let (config, problems) = Config::parse(&content);
+        for problem in problems {
+            eprintln!("{}: {}", path.display(), problem);
+        }
+        config
+    }
+
+    /// Writes the settings, into the lines of the existing file if there is one.
+    pub fn save(&self) -> Result<(), String> {
+        let path = storage::config_dir().join(CONFIG_FILE);
+        let content = match std::fs::read_to_string(&path) {
+            Ok(content) => self.update_toml(&content),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => self.to_toml(),
+            Err(error) => return Err(format!("{}: {}", path.display(), error)),
+        };
+        storage::write_config(CONFIG_FILE, &content)
+    }
+
+    /// Parses the flat subset of TOML written by `to_toml`, lines like `width = 1000`.
+    pub fn parse(content: &str) -> (Self, Vec<String>) {
+        let mut config = Config::default();
+        let problems = parse_lines(content, |key, value| {
+            match key {
+                "width" => config.width = parse(value)?,
+                "height" => config.height = parse(value)?,
+                "theme" => config.theme = value.to_string(),
+                "difficulty" => config.difficulty = Difficulty::from_name(value).ok_or(invalid(value))?,
+                "steps_per_frame" => config.steps_per_frame = parse(value)?,
+                "show_available" => config.show_available = parse(value)?,
+                "highlight_relevant" => config.highlight_relevant = parse(value)?,
+                "shape_cues" => config.shape_cues = parse(value)?,
+                "font_scale" => config.font_scale = parse(value)?,
+                "digit_first" => config.digit_first = parse(value)?,
+                "seed" => config.seed = Some(parse(value)?),
+                _ => return Err(format!("unknown setting {}", key)),
+            }
+            Ok(())
+        });
+        (config, problems)
+    }
+
+    pub fn to_toml(&self) -> String {
+        let seed = match self.seed {
+            Some(seed) => format!("seed = {}", seed),
+            None => "# seed = 42".to_string(),
+        };
+        format!(
+            "# Settings of nannou-sudoku, written when they change in the visualizer.\n\
+             width = {}\n\
+             height = {}\n\
+             # dark, light, discord, high contrast, colorblind dark, colorblind light\n\
+             # or the name of a theme in the themes directory\n\
+             theme = \"{}\"\n\
+             # easy, medium, hard or toughest\n\
+             difficulty = \"{}\"\n\
+             steps_per_frame = {:?}\n\
+             show_available = {}\n\
+             highlight_relevant = {}\n\
+             # Underline player digits, outline solver digits and hatch conflicts.\n\
+             shape_cues = {}\n\
+             # Size of the text, 0.75 to 1.5\n\
+             font_scale = {:?}\n\
+             # Pick a digit on the digit pad first, then the tiles to put it in.\n\
+             digit_first = {}\n\
+             # Same puzzles and solver runs in every session, random if not set.\n\
+             {}\n",
+            self.width,
+            self.height,
+            self.theme,
+            self.difficulty.collection(),
+            self.steps_per_frame,
+            self.show_available,
+            self.highlight_relevant,
+            self.shape_cues,
+            self.font_scale,
+            self.digit_first,
+            seed,
+        )
+    }
+
+    /// Every setting with its value as written to the file, `None` for a seed that is not set.
+    fn values(&self) -> [(&'static str, Option<String>); 11] {
+        [
+            ("width", Some(self.width.to_string())),
+            ("height", Some(self.height.to_string())),
+            ("theme", Some(format!("\"{}\"", self.theme))),
+            ("difficulty", Some(format!("\"{}\"", self.difficulty.collection()))),
+            ("steps_per_frame", Some(format!("{:?}", self.steps_per_frame))),
+            ("show_available", Some(self.show_available.to_string())),
+            ("highlight_relevant", Some(self.highlight_relevant.to_string())),
+            ("shape_cues", Some(self.shape_cues.to_string())),
+            ("font_scale", Some(format!("{:?}", self.font_scale))),
+            ("digit_first", Some(self.digit_first.to_string())),
+            ("seed", self.seed.map(|seed| seed.to_string())),
+        ]
+    }
+
+    /// `content` of an existing file with the settings that differ from it written into
+    /// their lines. Comments, the order and lines that are not understood stay as they
+    /// are, changed settings missing from the file are appended.
+    pub fn update_toml(&self, content: &str) -> String {
+        let (old, _) = Config::parse(content);
+        let changed: Vec<_> = self.values().into_iter().zip(old.values()).filter(|(new, old)| new != old).map(|(new, _)| new).collect();
+        let mut written = vec![false; changed.len()];
+        let mut lines = Vec::new();
+        for line in content.lines() {
+            let setting = line[..comment_start(line)].split_once('=').map(|(key, _)| key.trim());
+            let Some(i) = changed.iter().position(|(key, _)| Some(*key) == setting) else {
+                lines.push(line.to_string());
+                continue;
+            };
+            written[i] = true;
+            if let (key, Some(value)) = &changed[i] {
+                let indent = &line[..line.len() - line.trim_start().len()];
+                lines.push(format!("{}{} = {}{}", indent, key, value, comment(line)));
+            }
+        }
+        for ((key, value), written) in changed.iter().zip(written) {
+            if let (Some(value), false) = (value, written) {
+                lines.push(format!("{} = {}", key, value));
+            }
+        }
+        lines.join("\n") + "\n"
+    }
+
+    /// Takes the settings that differ between `before` and `after` from `after`, so
+    /// settings given on the command line are only saved once they are changed.
+    pub fn take_changes(&mut self, before: &Config, after: &Config) {
+        fn take<T: PartialEq + Clone>(setting: &mut T, before: &T, after: &T) {
+            if before != after {
+                *setting = after.clone();
+            }
+        }
+        take(&mut self.width, &before.width, &after.width);
+        take(&mut self.height, &before.height, &after.height);
+        take(&mut self.theme, &before.theme, &after.theme);
+        take(&mut self.difficulty, &before.difficulty, &after.difficulty);
+        take(&mut self.steps_per_frame, &before.steps_per_frame, &after.steps_per_frame);
+        take(&mut self.show_available, &before.show_available, &after.show_available);
+        take(&mut self.highlight_relevant, &before.highlight_relevant, &after.highlight_relevant);
+        take(&mut self.shape_cues, &before.shape_cues, &after.shape_cues);
+        take(&mut self.font_scale, &before.font_scale, &after.font_scale);
+        take(&mut self.digit_first, &before.digit_first, &after.digit_first);
+        take(&mut self.seed, &before.seed, &after.seed);
+    }
+}
+
+/// Calls `set` with the key and value of every line like `key = value` or `key = "value"`,
+/// skipping comments. Returns the problems, with their line.
+pub fn parse_lines(content: &str, mut set: impl FnMut(&str, &str) -> Result<(), String>) -> Vec<String> {
+    let mut problems = Vec::new();
+    for (number, line) in content.lines().enumerate() {
+        let line = line[..comment_start(line)].trim();
+        if line.is_empty() {
+            continue;
+        }
+        let result = match line.split_once('=') {
+            Some((key, value)) => {
+                let value = value.trim();
+                set(key.trim(), value.strip_prefix('"').and_then(|value| value.strip_suffix('"')).unwrap_or(value))
+            }
+            None => Err("expected key = value".to_string()),
+        };
+        if let Err(problem) = result {
+            problems.push(format!("line {}: {}", number + 1, problem));
+        }
+    }
+    problems
+}
+
+/// Index of the `#` that starts the comment of a line, the end of the line if it has none.
+/// A `#` in quotes is part of the value.
+fn comment_start(line: &str) -> usize {
+    let mut quoted = false;
+    line.char_indices()
+        .find(|&(_, c)| {
+            quoted ^= c == '"';
+            c == '#' && !quoted
+        })
+        .map_or(line.len(), |(i, _)| i)
+}
+
+/// The comment at the end of a line with the space in front of it, empty if it has none.
+fn comment(line: &str) -> &str {
+    let value = &line[..comment_start(line)];
+    &line[value.trim_end().len()..]
+}
+
+pub fn parse<T: FromStr>(value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| invalid(value))
+}
+
+pub fn invalid(value: &str) -> String {
+    format!("invalid value {}", value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn toml_round_trip() {
+        let config = Config {
+            width: 1280,
+            height: 720,
+            theme: "Solarized Dark".to_string(),
+            difficulty: Difficulty::VeryHard,
+            steps_per_frame: 12.5,
+            show_available: false,
+            highlight_relevant: true,
+            shape_cues: true,
+            font_scale: 1.25,
+            digit_first: true,
+            seed: Some(7),
+        };
+        let (parsed, problems) = Config::parse(&config.to_toml());
+        assert!(problems.is_empty(), "{:?}", problems);
+        assert_eq!(parsed, config);
+        assert_eq!(Config::parse(&Config::default().to_toml()), (Config::default(), Vec::new()));
+    }
+
+    #[test]
+    fn reports_problems() {
+        let (config, problems) = Config::parse("width = wide\ntheme = \"#1 Theme\" # comment\ncolor = 3\nfullscreen\n");
+        assert_eq!(problems.len(), 3, "{:?}", problems);
+        assert_eq!(config.width, Config::default().width);
+        assert_eq!(config.theme, "#1 Theme");
+    }
+
+    #[test]
+    fn updates_only_changed_lines() {
+        let content = "# my settings\nwidth = 1200 # laptop\n  theme = \"Light\"\nwidth = wide\ncolor = 3\nfullscreen\n";
+        let (config, _) = Config::parse(content);
+        assert_eq!(config.update_toml(content), content);
+        let changed = Config { theme: "Dark".to_string(), height: 600, ..config };
+        assert_eq!(
+            changed.update_toml(content),
+            "# my settings\nwidth = 1200 # laptop\n  theme = \"Dark\"\nwidth = wide\ncolor = 3\nfullscreen\nheight = 600\n"
+        );
+        let changed = Config { width: 1000, ..changed };
+        assert_eq!(Config::parse(&changed.update_toml(content)).0, changed);
+    }
+
+    #[test]
+    fn takes_only_changed_settings() {
+        let mut config = Config::default();
+        let before = Config { width: 600, theme: "Light".to_string(), ..Config::default() };
+        let after = Config { difficulty: Difficulty::Hard, ..before.clone() };
+        config.take_changes(&before, &after);
+        assert_eq!(config, Config { difficulty: Difficulty::Hard, ..Config::default() });
+    }
+}
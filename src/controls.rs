@@ -0,0 +1,145 @@
+use nannou::prelude::*;
+
+use crate::bindings::Action;
+use crate::sudoku::Difficulty;
+
+/// What a click on a control of the side panel does.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Click {
+    /// The same as pressing a key of the action.
+    Action(Action),
+    Difficulty(Difficulty),
+    /// Switches to the previous or next theme.
+    Theme(isize),
+    /// Sets the speed of the solvers to the position on the slider.
+    Speed,
+    /// Digit of the digit pad, 0 erases.
+    Digit(u8),
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ControlKind {
+    Button,
+    /// Check box of a setting that is on or off.
+    Toggle(bool),
+    /// Option of a selector, true for the selected one.
+    Choice(bool),
+    /// Slider with its knob between 0 and 1.
+    Slider(f32),
+}
+
+/// Button, check box, option or slider of the side panel. The rect is set when the
+/// panel is laid out.
+pub struct Control {
+    pub kind: ControlKind,
+    pub text: String,
+    /// Keys of the control, shown while the mouse is over it.
+    pub hint: String,
+    pub click: Click,
+    pub enabled: bool,
+    /// Share of the width of its row, relative to the other controls of the row.
+    pub weight: f32,
+    pub rect: Rect,
+}
+
+impl Control {
+    pub fn new(kind: ControlKind, text: &str, click: Click) -> Self {
+        Control {
+            kind,
+            text: text.to_string(),
+            hint: String::new(),
+            click,
+            enabled: true,
+            weight: 1.0,
+            rect: Rect::from_w_h(0.0, 0.0),
+        }
+    }
+
+    pub fn hint(mut self, hint: String) -> Self {
+        self.hint = hint;
+        self
+    }
+
+    pub fn enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+
+    pub fn weight(mut self, weight: f32) -> Self {
+        self.weight = weight;
+        self
+    }
+
+    /// Area of the track of a slider, right of its text.
+    pub fn track(&self) -> Rect {
+        let left = self.rect.left() + self.rect.w() * 0.35;
+        Rect::from_corners(pt2(left, self.rect.bottom()), pt2(self.rect.right() - 6.0, self.rect.top()))
+    }
+
+    /// Position on the track of a slider under the given x coordinate, between 0 and 1.
+    pub fn slider_position(&self, x: f32) -> f32 {
+        let track = self.track();
+        ((x - track.left()) / track.w()).clamp(0.0, 1.0)
+    }
+}
+
+/// Places the controls of a row side by side in the rect by their weight, leaving a small
+/// gap between them.
+pub fn place_row(controls: &mut [Control], rect: Rect) {
+    let gap = 4.0;
+    let total: f32 = controls.iter().map(|control| control.weight).sum();
+    let unit = (rect.w() - gap * (controls.len() as f32 - 1.0)) / total;
+    let mut left = rect.left();
+    for control in controls.iter_mut() {
+        let width = unit * control.weight;
+        control.rect = Rect::from_corners(pt2(left, rect.bottom() + gap / 2.0), pt2(left + width, rect.top() - gap / 2.0));
+        left += width + gap;
+    }
+}
+
+/// Enabled control under the point.
+pub fn control_at(controls: &[Control], point: Point2) -> Option<&Control> {
+    controls.iter().find(|control| control.enabled && control.rect.contains(point))
+}
+
+/// Slider position of a speed of the solvers. The slider is logarithmic from 0.01 to
+/// 10000 steps per frame, so slow and fast speeds get the same room.
+pub fn speed_position(steps_per_frame: f32) -> f32 {
+    ((steps_per_frame.log10() + 2.0) / 6.0).clamp(0.0, 1.0)
+}
+
+pub fn speed_at(position: f32) -> f32 {
+    10f32.powf(position * 6.0 - 2.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn row_layout_and_hit_testing() {
+        let mut controls = [
+            Control::new(ControlKind::Button, "A", Click::Theme(-1)),
+            Control::new(ControlKind::Button, "B", Click::Theme(1)).enabled(false),
+            Control::new(ControlKind::Slider(0.5), "C", Click::Speed).weight(2.0),
+        ];
+        place_row(&mut controls, Rect::from_corners(pt2(0.0, 0.0), pt2(408.0, 20.0)));
+        assert_eq!(controls[1].rect.left(), 104.0);
+        assert_eq!(controls[2].rect.left(), 208.0);
+        assert_eq!(controls[2].rect.right(), 408.0);
+        assert_eq!(control_at(&controls, pt2(50.0, 10.0)).map(|control| control.click), Some(Click::Theme(-1)));
+        assert!(control_at(&controls, pt2(150.0, 10.0)).is_none());
+        assert!(control_at(&controls, pt2(102.0, 10.0)).is_none());
+        assert_eq!(controls[2].slider_position(0.0), 0.0);
+        assert_eq!(controls[2].slider_position(1000.0), 1.0);
+    }
+
+    #[test]
+    fn speed_slider_is_logarithmic() {
+        assert_eq!(speed_position(1.0), 2.0 / 6.0);
+        assert_eq!(speed_position(0.001), 0.0);
+        for steps in [0.01, 0.5, 1.0, 250.0, 10000.0] {
+            assert!((speed_at(speed_position(steps)) / steps - 1.0).abs() < 1e-3, "{}", steps);
+        }
+    }
+}
@@ -0,0 +1,253 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::storage;
+use crate::sudoku::{Difficulty, PuzzleId};
+
+const DAILY_FILE: &str = "daily.csv";
+
+/// A day of the calendar, counted from 1970-01-01. Days change at midnight UTC, so
+/// everyone gets the same daily puzzle at the same time.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct Day(pub i64);
+
+impl Day {
+    pub fn today() -> Self {
+        let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_secs());
+        Day((seconds / 86400) as i64)
+    }
+
+    /// Day of a date of the proleptic Gregorian calendar, months and days start at 1.
+    pub fn from_date(year: i64, month: u32, day: u32) -> Self {
+        let year = if month <= 2 { year - 1 } else { year };
+        let era = year.div_euclid(400);
+        let year_of_era = year - era * 400;
+        let month = month as i64;
+        let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day as i64 - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        Day(era * 146097 + day_of_era - 719468)
+    }
+
+    /// Year, month and day of the month.
+    pub fn date(self) -> (i64, u32, u32) {
+        let days = self.0 + 719468;
+        let era = days.div_euclid(146097);
+        let day_of_era = days - era * 146097;
+        let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let shifted_month = (5 * day_of_year + 2) / 153;
+        let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+        let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 } as u32;
+        let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+        (year, month, day)
+    }
+
+    /// Day of the week, 0 for Monday.
+    pub fn weekday(self) -> usize {
+        (self.0 + 3).rem_euclid(7) as usize
+    }
+
+    pub fn offset(self, days: i64) -> Self {
+        Day(self.0 + days)
+    }
+
+    /// First day of the month `months` after the month of the day.
+    fn month_after(self, months: i64) -> Self {
+        let (year, month, _) = self.date();
+        let index = year * 12 + month as i64 - 1 + months;
+        Day::from_date(index.div_euclid(12), index.rem_euclid(12) as u32 + 1, 1)
+    }
+
+    pub fn month_start(self) -> Self {
+        self.month_after(0)
+    }
+
+    pub fn month_length(self) -> i64 {
+        self.month_after(1).0 - self.month_after(0).0
+    }
+
+    /// The same day of another month, or its last day if the month is shorter.
+    pub fn add_months(self, months: i64) -> Self {
+        let first = self.month_after(months);
+        first.offset(self.date().2.min(first.month_length() as u32) as i64 - 1)
+    }
+
+    /// Puzzle of the day. The week gets harder from easy puzzles on Monday to the toughest
+    /// on Sunday, the line is picked by a hash of the day that does not change between
+    /// builds, unlike the generators of `rand`.
+    pub fn puzzle(self) -> PuzzleId {
+        let difficulty = match self.weekday() {
+            0 | 1 => Difficulty::Easy,
+            2 | 3 => Difficulty::Medium,
+            4 | 5 => Difficulty::Hard,
+            _ => Difficulty::VeryHard,
+        };
+        let count = difficulty.puzzles().lines().count();
+        let line = (splitmix64(self.0 as u64) % count as u64) as usize + 1;
+        PuzzleId { difficulty, line }
+    }
+}
+
+/// The SplitMix64 mixing function, spreads neighboring days over the whole collection.
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+impl fmt::Display for Day {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (year, month, day) = self.date();
+        write!(f, "{:04}-{:02}-{:02}", year, month, day)
+    }
+}
+
+impl FromStr for Day {
+    type Err = String;
+
+    fn from_str(date: &str) -> Result<Self, String> {
+        let invalid = || format!("a date looks like 2024-03-31: {}", date);
+        let mut fields = date.splitn(3, '-');
+        let mut field = || fields.next().and_then(|field| field.parse::<i64>().ok()).ok_or_else(invalid);
+        let (year, month, day) = (field()?, field()?, field()?);
+        if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+            return Err(invalid());
+        }
+        let parsed = Day::from_date(year, month as u32, day as u32);
+        match parsed.date() == (year, month as u32, day as u32) {
+            true => Ok(parsed),
+            false => Err(invalid()),
+        }
+    }
+}
+
+/// A solved daily puzzle.
+pub struct DailyRecord {
+    pub time: Duration,
+    pub hints: u32,
+    /// Day the puzzle was solved on, later than its own day if it was caught up on.
+    pub completed: Day,
+}
+
+/// The daily puzzles the player solved, kept in the data directory as a CSV file.
+#[derive(Default)]
+pub struct Daily {
+    days: BTreeMap<Day, DailyRecord>,
+}
+
+impl Daily {
+    pub fn load() -> Self {
+        let parse = |line: &str| {
+            let fields: Vec<&str> = line.split(',').collect();
+            let [day, completed, seconds, hints] = fields[..] else {
+                return None;
+            };
+            let record = DailyRecord {
+                time: Duration::from_secs(seconds.parse().ok()?),
+                hints: hints.parse().ok()?,
+                completed: completed.parse().ok()?,
+            };
+            Some((day.parse().ok()?, record))
+        };
+        Daily {
+            days: storage::read_lines(DAILY_FILE).iter().filter_map(|line| parse(line)).collect(),
+        }
+    }
+
+    pub fn get(&self, day: Day) -> Option<&DailyRecord> {
+        self.days.get(&day)
+    }
+
+    /// Records the daily puzzle of `day` as solved today. Only the first solution counts.
+    pub fn complete(&mut self, day: Day, time: Duration, hints: u32) -> Result<(), String> {
+        if self.days.contains_key(&day) {
+            return Ok(());
+        }
+        let record = DailyRecord { time, hints, completed: Day::today() };
+        storage::append_line(DAILY_FILE, &format!("{},{},{},{}", day, record.completed, time.as_secs(), hints))?;
+        self.days.insert(day, record);
+        Ok(())
+    }
+
+    fn on_time(&self, day: Day) -> bool {
+        self.days.get(&day).is_some_and(|record| record.completed == day)
+    }
+
+    /// Days in a row whose puzzle was solved on the day itself, up to today. A streak is
+    /// kept until today ends, even if today's puzzle is not solved yet.
+    pub fn streak(&self, today: Day) -> usize {
+        let last = if self.on_time(today) { today } else { today.offset(-1) };
+        (0i64..).take_while(|&days| self.on_time(last.offset(-days))).count()
+    }
+
+    pub fn best_streak(&self) -> usize {
+        let mut best = 0;
+        let mut current = 0;
+        let mut previous = None;
+        for (&day, _) in self.days.iter().filter(|(&day, record)| record.completed == day) {
+            current = if previous == Some(day.offset(-1)) { current + 1 } else { 1 };
+            best = best.max(current);
+            previous = Some(day);
+        }
+        best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dates() {
+        assert_eq!(Day::from_date(1970, 1, 1), Day(0));
+        assert_eq!(Day::from_date(2000, 3, 1).offset(-1).date(), (2000, 2, 29));
+        assert_eq!(Day::from_date(1900, 3, 1).offset(-1).date(), (1900, 2, 28));
+        for days in [-700_000, -1, 0, 59, 11_016, 20_744, 1_000_000] {
+            assert_eq!(Day::from_date(Day(days).date().0, Day(days).date().1, Day(days).date().2), Day(days));
+            assert_eq!(Day(days).to_string().parse::<Day>(), Ok(Day(days)));
+        }
+        assert_eq!(Day::from_date(2026, 10, 18).weekday(), 6);
+        assert_eq!(Day::from_date(2024, 1, 31).add_months(1).to_string(), "2024-02-29");
+        assert_eq!(Day::from_date(2024, 12, 15).add_months(1).to_string(), "2025-01-15");
+        for date in ["2023-02-29", "2024-13-01", "2024-1", "today"] {
+            assert!(date.parse::<Day>().is_err(), "{}", date);
+        }
+    }
+
+    #[test]
+    fn daily_puzzle_is_valid_and_deterministic() {
+        let monday = Day::from_date(2026, 10, 12);
+        for days in 0..7 {
+            let id = monday.offset(days).puzzle();
+            assert_eq!(id, monday.offset(days).puzzle());
+            assert!(id.puzzle().is_some(), "{}", id);
+        }
+        assert_eq!(monday.puzzle().difficulty, Difficulty::Easy);
+        assert_eq!(monday.offset(6).puzzle().difficulty, Difficulty::VeryHard);
+        assert_ne!(monday.puzzle(), monday.offset(7).puzzle());
+        // Pinned, so a change of the puzzle of a day does not go unnoticed.
+        assert_eq!(monday.puzzle().to_string(), "easy:44106");
+        assert_eq!(splitmix64(0), 0xe220_a839_7b1d_cdaf);
+    }
+
+    #[test]
+    fn streaks() {
+        let today = Day::from_date(2026, 10, 18);
+        let solved = |day: Day, completed: Day| (day, DailyRecord { time: Duration::ZERO, hints: 0, completed });
+        let mut daily = Daily {
+            days: [-9, -8, -7, -6, -3, -2, -1]
+                .map(|days| solved(today.offset(days), today.offset(days)))
+                .into_iter()
+                .chain([solved(today.offset(-4), today.offset(-2))])
+                .collect(),
+        };
+        assert_eq!(daily.streak(today), 3);
+        assert_eq!(daily.best_streak(), 4);
+        daily.days.insert(today, DailyRecord { time: Duration::ZERO, hints: 0, completed: today });
+        assert_eq!(daily.streak(today), 4);
+        assert_eq!(daily.streak(today.offset(2)), 0);
+    }
+}
@@ -0,0 +1,420 @@
+use crate::solver::{Breakpoint, Direction, Solver, SolverStats, TraceStep};
+use crate::sudoku::{SolverState, Tile};
+
+/// Nine tiles that must contain every digit exactly once.
+pub type House = [usize; 9];
+
+/// Rows, columns and boxes of a standard sudoku.
+pub fn standard_houses() -> Vec<House> {
+    let mut houses = row_and_column_houses();
+    houses.extend((0..9).map(|b| std::array::from_fn(|i| b / 3 * 27 + b % 3 * 3 + i / 3 * 9 + i % 3)));
+    houses
+}
+
+fn row_and_column_houses() -> Vec<House> {
+    let rows = (0..9).map(|r| std::array::from_fn(|i| r * 9 + i));
+    let columns = (0..9).map(|c| std::array::from_fn(|i| i * 9 + c));
+    rows.chain(columns).collect()
+}
+
+/// The two main diagonals of the X-sudoku variant.
+pub fn diagonal_houses() -> Vec<House> {
+    vec![std::array::from_fn(|i| i * 10), std::array::from_fn(|i| i * 8 + 8)]
+}
+
+/// Rows and columns plus nine irregular regions instead of the boxes. `regions`
+/// holds the region, 0 to 8, of every tile. Returns `None` unless every region has nine tiles.
+pub fn jigsaw_houses(regions: &[u8; 81]) -> Option<Vec<House>> {
+    let mut houses = row_and_column_houses();
+    for region in 0..9 {
+        let tiles: Vec<usize> = (0..81).filter(|i| regions[*i] == region).collect();
+        houses.push(tiles.try_into().ok()?);
+    }
+    Some(houses)
+}
+
+/// What happened during a single step of the search.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum DlxEvent {
+    /// A column was chosen and covered.
+    Cover(usize),
+    /// A row was added to the partial solution.
+    Select(usize),
+    /// A row was removed from the partial solution.
+    Deselect(usize),
+    /// All columns are covered, the selected rows form a solution.
+    Solution,
+    /// The search space is exhausted.
+    Done,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Phase {
+    Choose,
+    Try,
+    Backtrack,
+    Done,
+}
+
+/// Knuth's Algorithm X on dancing links. The search runs one step at a time,
+/// so it can be animated as well as run to completion.
+#[derive(Clone)]
+pub struct Dlx {
+    left: Vec<usize>,
+    right: Vec<usize>,
+    up: Vec<usize>,
+    down: Vec<usize>,
+    column: Vec<usize>,
+    row: Vec<usize>,
+    size: Vec<usize>,
+    /// Row node chosen on every level of the search.
+    stack: Vec<usize>,
+    phase: Phase,
+}
+
+const ROOT: usize = 0;
+
+impl Dlx {
+    /// Creates the matrix with the given number of columns, all of which must be covered exactly once.
+    pub fn new(columns: usize) -> Self {
+        let nodes = columns + 1;
+        let mut dlx = Dlx {
+            left: (0..nodes).map(|i| (i + nodes - 1) % nodes).collect(),
+            right: (0..nodes).map(|i| (i + 1) % nodes).collect(),
+            up: (0..nodes).collect(),
+            down: (0..nodes).collect(),
+            column: (0..nodes).collect(),
+            row: vec![usize::MAX; nodes],
+            size: vec![0; nodes],
+            stack: Vec::new(),
+            phase: Phase::Choose,
+        };
+        dlx.size[ROOT] = usize::MAX;
+        dlx
+    }
+
+    /// Adds a row covering the given columns, numbered from 0.
+    pub fn add_row(&mut self, row: usize, columns: &[usize]) {
+        let first = self.left.len();
+        for (i, column) in columns.iter().enumerate() {
+            let header = column + 1;
+            let node = first + i;
+            self.left.push(if i == 0 { first + columns.len() - 1 } else { node - 1 });
+            self.right.push(if i == columns.len() - 1 { first } else { node + 1 });
+            self.up.push(self.up[header]);
+            self.down.push(header);
+            self.column.push(header);
+            self.row.push(row);
+            let last = self.up[header];
+            self.down[last] = node;
+            self.up[header] = node;
+            self.size[header] += 1;
+        }
+    }
+
+    fn cover(&mut self, column: usize) {
+        let (left, right) = (self.left[column], self.right[column]);
+        self.right[left] = right;
+        self.left[right] = left;
+        let mut i = self.down[column];
+        while i != column {
+            let mut j = self.right[i];
+            while j != i {
+                let (up, down) = (self.up[j], self.down[j]);
+                self.down[up] = down;
+                self.up[down] = up;
+                self.size[self.column[j]] -= 1;
+                j = self.right[j];
+            }
+            i = self.down[i];
+        }
+    }
+
+    fn uncover(&mut self, column: usize) {
+        let mut i = self.up[column];
+        while i != column {
+            let mut j = self.left[i];
+            while j != i {
+                self.size[self.column[j]] += 1;
+                let (up, down) = (self.up[j], self.down[j]);
+                self.down[up] = j;
+                self.up[down] = j;
+                j = self.left[j];
+            }
+            i = self.up[i];
+        }
+        let (left, right) = (self.left[column], self.right[column]);
+        self.right[left] = column;
+        self.left[right] = column;
+    }
+
+    /// Rows of the current partial solution.
+    pub fn selected_rows(&self) -> impl Iterator<Item = usize> + '_ {
+        self.stack.iter().filter(|node| self.column[**node] != **node).map(|node| self.row[*node])
+    }
+
+    /// Rows that cover the column and were not removed by the partial solution.
+    /// Only meaningful while the column itself is not covered.
+    pub fn column_rows(&self, column: usize) -> impl Iterator<Item = usize> + '_ {
+        let header = column + 1;
+        std::iter::successors(Some(self.down[header]), |node| Some(self.down[*node]))
+            .take_while(move |node| *node != header)
+            .map(|node| self.row[node])
+    }
+
+    /// Number of columns that are not covered yet.
+    pub fn uncovered_columns(&self) -> usize {
+        let mut count = 0;
+        let mut column = self.right[ROOT];
+        while column != ROOT {
+            count += 1;
+            column = self.right[column];
+        }
+        count
+    }
+
+    /// Advances the search by a single step.
+    pub fn step(&mut self) -> DlxEvent {
+        match self.phase {
+            Phase::Choose => {
+                if self.right[ROOT] == ROOT {
+                    self.phase = Phase::Backtrack;
+                    return DlxEvent::Solution;
+                }
+                let mut column = self.right[ROOT];
+                let mut best = column;
+                while column != ROOT {
+                    if self.size[column] < self.size[best] {
+                        best = column;
+                    }
+                    column = self.right[column];
+                }
+                self.cover(best);
+                self.stack.push(self.down[best]);
+                self.phase = Phase::Try;
+                DlxEvent::Cover(best - 1)
+            }
+            Phase::Try => {
+                let node = *self.stack.last().unwrap();
+                let header = self.column[node];
+                if node == header {
+                    // Every row of the column was tried.
+                    self.uncover(header);
+                    self.stack.pop();
+                    self.phase = match self.stack.is_empty() {
+                        true => Phase::Done,
+                        false => Phase::Backtrack,
+                    };
+                    return self.step();
+                }
+                let mut j = self.right[node];
+                while j != node {
+                    self.cover(self.column[j]);
+                    j = self.right[j];
+                }
+                self.phase = Phase::Choose;
+                DlxEvent::Select(self.row[node])
+            }
+            Phase::Backtrack => {
+                let Some(node) = self.stack.pop() else {
+                    self.phase = Phase::Done;
+                    return DlxEvent::Done;
+                };
+                let mut j = self.left[node];
+                while j != node {
+                    self.uncover(self.column[j]);
+                    j = self.left[j];
+                }
+                self.stack.push(self.down[node]);
+                self.phase = Phase::Try;
+                DlxEvent::Deselect(self.row[node])
+            }
+            Phase::Done => DlxEvent::Done,
+        }
+    }
+
+    /// Runs the search, calling `on_solution` with the rows of every solution until it returns false.
+    /// Returns the number of solutions found.
+    pub fn solve_all(&mut self, mut on_solution: impl FnMut(&[usize]) -> bool) -> usize {
+        let mut count = 0;
+        loop {
+            match self.step() {
+                DlxEvent::Solution => {
+                    count += 1;
+                    let rows: Vec<usize> = self.selected_rows().collect();
+                    if !on_solution(&rows) {
+                        return count;
+                    }
+                }
+                DlxEvent::Done => return count,
+                _ => (),
+            }
+        }
+    }
+}
+
+/// Exact cover formulation of a sudoku. Every tile and every digit of every house
+/// is a column, every candidate digit of a tile is a row.
+#[derive(Clone)]
+pub struct SudokuCover {
+    pub dlx: Dlx,
+}
+
+impl SudokuCover {
+    /// Builds the matrix for the filled tiles and the given houses. Digits that conflict
+    /// with a filled tile of the same house are left out.
+    pub fn new(tiles: &[Tile; 81], houses: &[House]) -> Self {
+        let mut houses_of: Vec<Vec<usize>> = vec![Vec::new(); 81];
+        for (h, house) in houses.iter().enumerate() {
+            for tile in house.iter() {
+                houses_of[*tile].push(h);
+            }
+        }
+        let mut used = vec![0u16; houses.len()];
+        for (h, house) in houses.iter().enumerate() {
+            for tile in house.iter() {
+                used[h] |= tiles[*tile].to_u16().map_or(0, |n| 1 << n);
+            }
+        }
+
+        let mut dlx = Dlx::new(81 + houses.len() * 9);
+        for (indx, tile) in tiles.iter().enumerate() {
+            let digits: Vec<u8> = match tile.to_u16() {
+                Some(n) => vec![n as u8],
+                None => (1..=9).filter(|n| houses_of[indx].iter().all(|h| used[*h] >> n & 1 == 0)).collect(),
+            };
+            for n in digits {
+                let mut columns = vec![indx];
+                columns.extend(houses_of[indx].iter().map(|h| 81 + h * 9 + n as usize - 1));
+                dlx.add_row(indx * 9 + n as usize - 1, &columns);
+            }
+        }
+        SudokuCover { dlx }
+    }
+
+    /// Tile and digit of a row of the matrix.
+    pub fn decode(row: usize) -> (usize, u8) {
+        (row / 9, (row % 9) as u8 + 1)
+    }
+
+    /// Writes the digits of the rows into the tiles.
+    pub fn solution(rows: &[usize]) -> [u8; 81] {
+        let mut digits = [0; 81];
+        for (indx, n) in rows.iter().map(|row| SudokuCover::decode(*row)) {
+            digits[indx] = n;
+        }
+        digits
+    }
+}
+
+/// The exact cover search as an animated solver. Shows the rows of the partial solution in the grid.
+#[derive(Clone)]
+pub struct DancingLinks {
+    cover: SudokuCover,
+    stats: SolverStats,
+    state: SolverState,
+    active_indx: Option<usize>,
+    /// Rows selected for empty tiles, the depth the breakpoints compare against.
+    depth: u64,
+}
+
+impl DancingLinks {
+    pub fn new(tiles: &[Tile; 81], houses: &[House]) -> Self {
+        DancingLinks {
+            cover: SudokuCover::new(tiles, houses),
+            stats: SolverStats::default(),
+            state: SolverState::Running,
+            active_indx: None,
+            depth: 0,
+        }
+    }
+}
+
+impl Solver for DancingLinks {
+    fn advance(&mut self, tiles: &mut [Tile; 81], steps: u32, breakpoints: &[Breakpoint]) -> bool {
+        let mut hit = false;
+        for _ in 0..steps {
+            if hit || !matches!(self.state, SolverState::Running) {
+                break;
+            }
+            // Selecting and deselecting rows is seen by the breakpoints like the backtracker
+            // filling and emptying a tile. Rows of filled tiles leave the tile as it is.
+            let step = |row, direction| {
+                let (cell, n) = SudokuCover::decode(row);
+                let filled = matches!(tiles[cell], Tile::Const(_) | Tile::PlayerVariable(_));
+                let (previous, value) = match direction {
+                    _ if filled => (tiles[cell], tiles[cell]),
+                    Direction::Forward => (Tile::Empty, Tile::SolverVariable(n)),
+                    Direction::Backward => (Tile::SolverVariable(n), Tile::Empty),
+                };
+                TraceStep { cell: cell as u8, previous, value, direction }
+            };
+            let step = match self.cover.dlx.step() {
+                DlxEvent::Solution => {
+                    self.state = SolverState::SolutionFound;
+                    None
+                }
+                DlxEvent::Done => {
+                    self.state = SolverState::NoSolution;
+                    None
+                }
+                DlxEvent::Select(row) => Some(step(row, Direction::Forward)),
+                DlxEvent::Deselect(row) => {
+                    self.stats.backtracks += 1;
+                    Some(step(row, Direction::Backward))
+                }
+                DlxEvent::Cover(_) => None,
+            };
+            self.stats.steps += 1;
+            if let Some(step) = step {
+                self.stats.visits[step.cell as usize] += 1;
+                self.stats.writes[step.cell as usize] += (step.value != step.previous) as u32;
+                self.depth = self.depth.wrapping_add_signed(step.depth_change());
+                hit = breakpoints.iter().any(|b| b.is_hit(&step, self.depth, self.stats.backtracks));
+            }
+        }
+        let mut digits = [0; 81];
+        self.active_indx = None;
+        for (indx, n) in self.cover.dlx.selected_rows().map(SudokuCover::decode) {
+            digits[indx] = n;
+            self.active_indx = Some(indx);
+        }
+        self.stats.depth = 0;
+        for (indx, tile) in tiles.iter_mut().enumerate() {
+            let value = match digits[indx] {
+                0 => Tile::Empty,
+                n => Tile::SolverVariable(n),
+            };
+            if let Tile::Empty | Tile::SolverVariable(_) = tile {
+                self.stats.depth += (value != Tile::Empty) as u64;
+                *tile = value;
+            }
+        }
+        hit
+    }
+
+    fn state(&self) -> SolverState {
+        self.state
+    }
+
+    fn stats(&self) -> &SolverStats {
+        &self.stats
+    }
+
+    fn active_cell(&self) -> Option<usize> {
+        self.active_indx
+    }
+
+    fn candidates(&self, indx: usize) -> Option<u16> {
+        let rows = self.cover.dlx.column_rows(indx);
+        Some(rows.map(SudokuCover::decode).fold(0, |candidates, (_, n)| candidates | 1 << n))
+    }
+
+    fn status(&self) -> Vec<String> {
+        vec![format!("Uncovered columns: {}", self.cover.dlx.uncovered_columns())]
+    }
+
+    fn boxed_clone(&self) -> Box<dyn Solver> {
+        Box::new(self.clone())
+    }
+}
@@ -1,80 +1,229 @@
+use crate::bindings::{Action, Scope};
+use crate::controls::Click;
+use crate::daily::Day;
 use crate::model::Model;
 use crate::sudoku::Difficulty;
 use crate::sudoku::SolverState;
 use crate::sudoku::Tile;
 use nannou::prelude::*;
 
+/// Selects the tile under the mouse. The selection stays when the mouse leaves the board,
+/// so it can be filled from the digit pad.
 pub fn mouse_moved(_app: &App, model: &mut Model, pos: Point2) {
     if model.sudoku.is_running() {
         return;
     }
-    let size = model.window_height.min(model.window_width) as f32 - 10.0;
-    if (pos.x + model.offset).abs() < size / 2.0 && pos.y.abs() < size / 2.0 {
-        let x = ((pos.x + model.offset + size / 2.0) / (size / 9.0)) as usize;
-        let y = ((pos.y + size / 2.0) / (size / 9.0)) as usize;
-        let selected_index = y * 9 + x;
-        model.selected = Some(selected_index);
-    } else {
-        model.selected = None;
+    if let Some(indx) = model.tile_at(pos) {
+        model.selected = Some(indx);
     }
 }
 
-pub fn handle_mouse_button_events(app: &App, window_height: u32, window_width: u32, model: &mut Model) {
-    if model.sudoku.is_running() {
+pub fn handle_mouse_button_events(app: &App, model: &mut Model) {
+    if let Some(pressed) = app.mouse.buttons.left().if_down() {
+        if model.shows_timeline() && model.timeline_rect().contains(pressed) {
+            let position = model.timeline_position(app.mouse.x);
+            model.sudoku.seek(position);
+            return;
+        }
+        if model.drag_speed_slider(pressed, app.mouse.x) {
+            return;
+        }
+    }
+    if model.sudoku.is_active()
+        || model.race.is_some()
+        || model.browser.is_some()
+        || model.calendar.is_some()
+        || model.show_stats
+        || model.game.paused
+        || model.game.finished
+    {
         return;
     }
-    let size = window_height.min(window_width) as f32;
     app.mouse.buttons.pressed().for_each(|button| match button {
-        (MouseButton::Left | MouseButton::Right, v)
-            if (v.x + model.offset).abs() < size / 2.0 && v.y.abs() < size / 2.0 =>
-        {
-            model.sudoku.clear_variables();
-            model.sudoku.reset_solver();
-            if let Some(selected) = model.selected {
-                model.sudoku.try_insert(selected, Tile::Empty);
-            }
-        }
+        // Digits picked on the pad are placed once per click, in `mouse_pressed`.
+        (MouseButton::Left, _) if model.digit_first && model.pad_digit.is_some() => (),
+        (MouseButton::Left | MouseButton::Right, v) if model.tile_at(v).is_some() => model.try_write_tile(Tile::Empty),
         _ => (),
     });
 }
 
+/// Keys while the puzzle browser is open.
+fn handle_browser_key(model: &mut Model, key: Key) {
+    let Some(browser) = model.browser.as_mut() else {
+        return;
+    };
+    match model.bindings.action(key, Scope::Browser) {
+        Some(Action::BrowseUp) => browser.move_cursor(-1),
+        Some(Action::BrowseDown) => browser.move_cursor(1),
+        Some(Action::BrowsePreviousPage) => browser.change_page(-1),
+        Some(Action::BrowseNextPage) => browser.change_page(1),
+        Some(Action::NextCollection) => browser.next_collection(),
+        Some(Action::FilterClues) => {
+            browser.clues = browser.clues.next();
+            browser.restart();
+        }
+        Some(Action::FilterGuesses) => {
+            browser.rating = browser.rating.next();
+            browser.restart();
+        }
+        Some(Action::FilterSolved) => {
+            browser.status = browser.status.next();
+            browser.restart();
+        }
+        Some(Action::LoadPuzzle) if !browser.query.is_empty() => browser.submit_query(),
+        Some(Action::LoadPuzzle) => model.open_selected_puzzle(),
+        _ if key == Key::Back => _ = browser.query.pop(),
+        _ if model.bindings.action(key, Scope::Board) == Some(Action::Browse) => model.toggle_browser(),
+        _ => {
+            let digit = match key {
+                Key::Key0 | Key::Numpad0 => Some('0'),
+                Key::Key1 | Key::Numpad1 => Some('1'),
+                Key::Key2 | Key::Numpad2 => Some('2'),
+                Key::Key3 | Key::Numpad3 => Some('3'),
+                Key::Key4 | Key::Numpad4 => Some('4'),
+                Key::Key5 | Key::Numpad5 => Some('5'),
+                Key::Key6 | Key::Numpad6 => Some('6'),
+                Key::Key7 | Key::Numpad7 => Some('7'),
+                Key::Key8 | Key::Numpad8 => Some('8'),
+                Key::Key9 | Key::Numpad9 => Some('9'),
+                _ => None,
+            };
+            if let Some(digit) = digit.filter(|_| browser.query.len() < 6) {
+                browser.query.push(digit);
+            }
+        }
+    }
+}
+
+/// Keys while the calendar of daily puzzles is shown.
+fn handle_calendar_key(model: &mut Model, key: Key) {
+    let Some(day) = model.calendar else {
+        return;
+    };
+    let action = model.bindings.action(key, Scope::Calendar);
+    model.calendar = match action {
+        Some(Action::PreviousDay) => Some(day.offset(-1)),
+        Some(Action::NextDay) => Some(day.offset(1)),
+        Some(Action::PreviousWeek) => Some(day.offset(-7)),
+        Some(Action::NextWeek) => Some(day.offset(7)),
+        Some(Action::PreviousMonth) => Some(day.add_months(-1)),
+        Some(Action::NextMonth) => Some(day.add_months(1)),
+        Some(Action::Today) => Some(Day::today()),
+        _ if model.bindings.action(key, Scope::Board) == Some(Action::Daily) => None,
+        _ => Some(day),
+    };
+    if action == Some(Action::PlayDaily) {
+        model.play_daily(day);
+    }
+}
+
 pub fn handle_key_pressed(app: &App, model: &mut Model, key: Key) {
-    match key {
-        Key::F11 => app.main_window().set_fullscreen(!app.main_window().is_fullscreen()),
-        Key::Return | Key::Space => {
+    let action = model.bindings.action(key, Scope::Board);
+    match action {
+        Some(Action::Quit) => return app.quit(),
+        Some(Action::Fullscreen) => return app.main_window().set_fullscreen(!app.main_window().is_fullscreen()),
+        _ => (),
+    }
+    if model.browser.is_some() {
+        handle_browser_key(model, key);
+        return;
+    }
+    if model.calendar.is_some() {
+        handle_calendar_key(model, key);
+        return;
+    }
+    if model.show_stats {
+        model.show_stats = action != Some(Action::Statistics);
+        return;
+    }
+    if let Some(action) = action {
+        perform(app, model, action);
+    }
+}
+
+/// Does what the action stands for, for a key or a control of the panel.
+fn perform(app: &App, model: &mut Model, action: Action) {
+    match action {
+        Action::Quit => app.quit(),
+        Action::Fullscreen => app.main_window().set_fullscreen(!app.main_window().is_fullscreen()),
+        Action::CursorUp => model.move_selection(0, 1),
+        Action::CursorDown => model.move_selection(0, -1),
+        Action::CursorLeft => model.move_selection(-1, 0),
+        Action::CursorRight => model.move_selection(1, 0),
+        Action::NextEmpty => model.select_next_empty(app.keys.mods.shift()),
+        Action::DigitFirst => model.toggle_digit_first(),
+        Action::Record if model.recorder.is_recording() => {
+            if app.main_window().await_capture_frame_jobs().is_err() {
+                eprintln!("timed out while waiting for frame captures to complete");
+            }
+            model.recorder.stop(model.fps());
+        }
+        Action::Record => model.recorder.start(),
+        Action::RecordingFormat if !model.recorder.is_recording() => model.recorder.format = model.recorder.format.next(),
+        Action::StartPause if model.race.is_some() => {
+            let race = model.race.as_mut().unwrap();
+            race.paused = !race.paused;
+        }
+        Action::Race if !model.sudoku.is_active() => model.toggle_race(),
+        Action::StartPause => match model.sudoku.state {
+            SolverState::Idle => {
+                model.sudoku.clear_variables();
+                model.sudoku.state = SolverState::Running;
+            }
+            SolverState::Running | SolverState::Paused => model.sudoku.toggle_pause(),
+            SolverState::NoSolution | SolverState::SolutionFound | SolverState::GaveUp => {
+                model.sudoku.clear_variables();
+                model.sudoku.reset_solver();
+            }
+        },
+        Action::StepBack => model.sudoku.step_back(),
+        Action::StepForward => model.sudoku.step_forward(),
+        Action::CellBreakpoint => {
+            if let Some(selected) = model.selected {
+                model.sudoku.toggle_cell_breakpoint(selected);
+            }
+        }
+        Action::DepthBreakpoint => model.sudoku.cycle_depth_breakpoint(),
+        Action::BacktrackBreakpoint => model.sudoku.cycle_backtrack_breakpoint(),
+        Action::ChangeAlgorithm if !model.sudoku.is_active() => {
             model.sudoku.clear_variables();
-            let state = match model.sudoku.state {
-                SolverState::Running | SolverState::NoSolution | SolverState::SolutionFound => SolverState::Idle,
-                SolverState::Idle  => SolverState::Running,
-            };
             model.sudoku.reset_solver();
-            model.sudoku.state = state;
+            model.sudoku.solver = model.sudoku.solver.next();
         }
-        Key::R => model.sudoku.load_random(),
-        Key::E if !model.sudoku.is_running() => {
+        Action::SolveInstantly if !model.sudoku.is_running() => model.sudoku.solve_instantly(),
+        Action::NewPuzzle => model.load_random(),
+        Action::Browse if !model.sudoku.is_active() && model.race.is_none() => model.toggle_browser(),
+        Action::PauseTimer => model.game.toggle_pause(),
+        Action::Hint => model.hint(),
+        Action::Statistics => model.show_stats = true,
+        Action::Daily if !model.sudoku.is_active() && model.race.is_none() => model.toggle_calendar(),
+        Action::ClearResult if !model.sudoku.is_running() => {
             model.sudoku.clear_variables();
             model.sudoku.reset_solver();
         }
-        Key::W if !model.sudoku.is_running() => {
+        Action::ClearSudoku if !model.sudoku.is_running() => {
             model.sudoku.tiles = [Tile::Empty; 81];
+            model.sudoku.id = None;
             model.sudoku.reset_solver();
         }
-        Key::Right if model.sudoku.difficulty != Difficulty::VeryHard => {
-            model.sudoku.difficulty = model.sudoku.difficulty.harder();
-            model.sudoku.load_random();
-        },
-        Key::Left if model.sudoku.difficulty != Difficulty::Easy => {
-            model.sudoku.difficulty = model.sudoku.difficulty.easier();
-            model.sudoku.load_random();
-        }
-        Key::T => model.theme.next(),
-        Key::Z => model.show_available = !model.show_available,
-        Key::U => model.higlight_relevant = !model.higlight_relevant,
+        Action::Harder if model.difficulty != Difficulty::VeryHard => model.set_difficulty(model.difficulty.harder()),
+        Action::Easier if model.difficulty != Difficulty::Easy => model.set_difficulty(model.difficulty.easier()),
+        Action::Theme => model.change_theme(1),
+        Action::ShowAvailable => model.show_available = !model.show_available,
+        Action::HighlightRelevant => model.higlight_relevant = !model.higlight_relevant,
+        Action::ShapeCues => model.shape_cues = !model.shape_cues,
+        Action::FontSmaller => model.change_font_scale(-1.0),
+        Action::FontLarger => model.change_font_scale(1.0),
+        Action::Heatmap => model.heatmap = model.heatmap.next(),
+        Action::Export => model.export(app.keys.mods.shift()),
         _ => (),
     }
 }
 
 pub fn handle_continious_key_inputs(app: &App, model: &mut Model) {
+    if model.browser.is_some() || model.calendar.is_some() || model.show_stats {
+        return;
+    }
     app.keys.down.iter().for_each(|key| match key {
         Key::Key1 | Key::Numpad1 => model.try_write_tile(Tile::PlayerVariable(1)),
         Key::Key2 | Key::Numpad2 => model.try_write_tile(Tile::PlayerVariable(2)),
@@ -86,14 +235,15 @@ pub fn handle_continious_key_inputs(app: &App, model: &mut Model) {
         Key::Key8 | Key::Numpad8 => model.try_write_tile(Tile::PlayerVariable(8)),
         Key::Key9 | Key::Numpad9 => model.try_write_tile(Tile::PlayerVariable(9)),
         Key::Key0 | Key::Numpad0 | Key::Back | Key::Delete => model.try_write_tile(Tile::Empty),
-        Key::Up => model
-            .sudoku
-            .change_steps_per_frame(1.0 + 5.0 * app.duration.since_prev_update.as_secs_f32()),
-        Key::Down => model.sudoku.change_steps_per_frame(
-            1.0 / (1.0 + 5.0 * app.duration.since_prev_update.as_secs_f32()),
-        ),
         _ => (),
     });
+    let speed = 1.0 + 5.0 * app.duration.since_prev_update.as_secs_f32();
+    if model.bindings.is_down(Action::Faster, &app.keys.down) {
+        model.sudoku.change_steps_per_frame(speed);
+    }
+    if model.bindings.is_down(Action::Slower, &app.keys.down) {
+        model.sudoku.change_steps_per_frame(1.0 / speed);
+    }
 }
 
 pub fn handle_mouse_wheel_events(
@@ -103,6 +253,41 @@ pub fn handle_mouse_wheel_events(
     _phase: TouchPhase,
 ) {
     if let MouseScrollDelta::LineDelta(_x, y) = dt {
-        model.sudoku.change_steps_per_frame(1.0 + 0.5 * y as f32);
+        match model.browser.as_mut() {
+            Some(browser) => browser.move_cursor(-y.round() as isize),
+            None => model.sudoku.change_steps_per_frame(1.0 + 0.5 * y),
+        }
+    }
+}
+
+/// Clicks on the controls of the panel, a row of the browser or a day of the calendar.
+pub fn mouse_pressed(app: &App, model: &mut Model, button: MouseButton) {
+    if button != MouseButton::Left {
+        return;
+    }
+    match model.control_at(app.mouse.position()) {
+        Some(Click::Action(action)) => return perform(app, model, action),
+        Some(Click::Difficulty(difficulty)) => return model.set_difficulty(difficulty),
+        Some(Click::Theme(steps)) => return model.change_theme(steps),
+        Some(Click::Digit(digit)) => return model.press_pad(digit),
+        // The speed slider is dragged while the button is down.
+        Some(Click::Speed) => return,
+        None => (),
     }
+    let editable = !model.sudoku.is_active() && model.race.is_none() && model.browser.is_none() && model.calendar.is_none();
+    if editable && model.tile_at(app.mouse.position()).is_some() && model.place_pad_digit() {
+        return;
+    }
+    if let Some(day) = model.calendar_day_at(app.mouse.position()) {
+        model.play_daily(day);
+        return;
+    }
+    if let Some(row) = model.browser_row_at(app.mouse.position()) {
+        model.browser.as_mut().unwrap().cursor = row;
+        model.open_selected_puzzle();
+    }
+}
+
+pub fn dropped_file(_app: &App, model: &mut Model, path: std::path::PathBuf) {
+    model.import(&path);
 }
\ No newline at end of file
@@ -0,0 +1,216 @@
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use nannou::color::{Rgb8, Rgba8};
+use nannou::prelude::*;
+
+use crate::board::{self, BoardOptions, Shape};
+use crate::dlx;
+use crate::sat;
+use crate::storage;
+use crate::sudoku::{Sudoku, Tile};
+use crate::theme::Theme;
+
+const EXPORTS_DIRECTORY: &str = "exports";
+/// Size of an A4 page in PDF points.
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const PAGE_MARGIN: f32 = 36.0;
+/// Width of a digit in Helvetica, relative to the font size.
+const DIGIT_WIDTH: f32 = 0.556;
+
+/// Copy of the grid for printing. Keeps the givens and, unless `givens_only` is set,
+/// the entries of the player and the solver.
+pub fn printable(sudoku: &Sudoku, givens_only: bool) -> Sudoku {
+    let mut printable = Sudoku::default();
+    for (tile, original) in printable.tiles.iter_mut().zip(sudoku.tiles.iter()) {
+        *tile = match original {
+            Tile::Const(_) => *original,
+            _ if givens_only => Tile::Empty,
+            _ => *original,
+        };
+    }
+    printable
+}
+
+fn export_options(show_notes: bool) -> BoardOptions {
+    BoardOptions {
+        show_available: show_notes,
+        show_active: false,
+        shape_cues: false,
+        font_scale: 1.0,
+    }
+}
+
+fn sorted_shapes(sudoku: &Sudoku, theme: &Theme, size: f32, show_notes: bool) -> Vec<Shape> {
+    let mut shapes = board::shapes(sudoku, theme, size, export_options(show_notes));
+    shapes.sort_by(|a, b| a.z().total_cmp(&b.z()));
+    shapes
+}
+
+fn svg_fill(color: Rgba8) -> String {
+    format!(
+        "fill=\"rgb({},{},{})\" fill-opacity=\"{:.3}\"",
+        color.red,
+        color.green,
+        color.blue,
+        color.alpha as f32 / 255.0
+    )
+}
+
+/// Renders the board as an SVG image of `size` x `size` pixels.
+pub fn svg(sudoku: &Sudoku, theme: &Theme, size: f32, show_notes: bool) -> String {
+    let mut svg = String::new();
+    let background = board::opaque(theme.background_color);
+    writeln!(svg, "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{0}\" viewBox=\"0 0 {0} {0}\">", size).unwrap();
+    writeln!(svg, "<rect width=\"{0}\" height=\"{0}\" {1}/>", size, svg_fill(background)).unwrap();
+    // Flips the y axis, SVG coordinates point down.
+    let point = |p: Vec2| vec2(p.x + size / 2.0, size / 2.0 - p.y);
+    for shape in sorted_shapes(sudoku, theme, size - 10.0, show_notes) {
+        match shape {
+            Shape::Rect { center, size, color, .. } => {
+                let corner = point(center) - size / 2.0;
+                writeln!(svg, "<rect x=\"{:.2}\" y=\"{:.2}\" width=\"{:.2}\" height=\"{:.2}\" {}/>", corner.x, corner.y, size.x, size.y, svg_fill(color)).unwrap();
+            }
+            Shape::Line { start, end, weight, color, .. } => {
+                let (start, end) = (point(start), point(end));
+                writeln!(
+                    svg,
+                    "<line x1=\"{:.2}\" y1=\"{:.2}\" x2=\"{:.2}\" y2=\"{:.2}\" stroke-width=\"{}\" stroke-linecap=\"square\" stroke=\"rgb({},{},{})\"/>",
+                    start.x, start.y, end.x, end.y, weight, color.red, color.green, color.blue
+                )
+                .unwrap();
+            }
+            Shape::Text { center, font_size, text, color, .. } => {
+                let center = point(center);
+                writeln!(
+                    svg,
+                    "<text x=\"{:.2}\" y=\"{:.2}\" font-family=\"sans-serif\" font-size=\"{:.2}\" text-anchor=\"middle\" dominant-baseline=\"central\" {}>{}</text>",
+                    center.x, center.y, nannou::text::pt_to_px(font_size), svg_fill(color), text
+                )
+                .unwrap();
+            }
+        }
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Number of columns and rows of puzzles on a page.
+fn page_grid(per_page: usize) -> (usize, usize) {
+    match per_page {
+        0 | 1 => (1, 1),
+        2 => (1, 2),
+        3 | 4 => (2, 2),
+        _ => (2, 3),
+    }
+}
+
+/// Color of `color` drawn over `under`, PDF colors without transparency need no extra state.
+fn flatten(color: Rgba8, under: Rgb8) -> Rgb8 {
+    let alpha = color.alpha as f32 / 255.0;
+    let mix = |a: u8, b: u8| (a as f32 * alpha + b as f32 * (1.0 - alpha)).round() as u8;
+    Rgb8::new(mix(color.red, under.red), mix(color.green, under.green), mix(color.blue, under.blue))
+}
+
+fn pdf_color(color: Rgb8) -> String {
+    format!("{:.3} {:.3} {:.3}", color.red as f32 / 255.0, color.green as f32 / 255.0, color.blue as f32 / 255.0)
+}
+
+fn pdf_text(content: &mut String, text: &str, x: f32, baseline: f32, font_size: f32, color: Rgb8) {
+    let width = DIGIT_WIDTH * font_size * text.chars().count() as f32;
+    let text = text.replace('\\', "\\\\").replace('(', "\\(").replace(')', "\\)");
+    writeln!(content, "{} rg BT /F1 {:.2} Tf {:.2} {:.2} Td ({}) Tj ET", pdf_color(color), font_size, x - width / 2.0, baseline, text).unwrap();
+}
+
+fn pdf_page(puzzles: &[(String, Sudoku)], theme: &Theme, show_notes: bool, per_page: usize) -> String {
+    let mut content = String::new();
+    let background = theme.background_color;
+    writeln!(content, "{} rg 0 0 {} {} re f", pdf_color(background), PAGE_WIDTH, PAGE_HEIGHT).unwrap();
+    let (columns, rows) = page_grid(per_page);
+    let cell_width = (PAGE_WIDTH - 2.0 * PAGE_MARGIN) / columns as f32;
+    let cell_height = (PAGE_HEIGHT - 2.0 * PAGE_MARGIN) / rows as f32;
+    let caption_size = 12.0;
+    let size = cell_width.min(cell_height - caption_size * 2.0) * 0.9;
+    for (i, (caption, sudoku)) in puzzles.iter().enumerate() {
+        let origin = vec2(
+            PAGE_MARGIN + cell_width * ((i % columns) as f32 + 0.5),
+            PAGE_HEIGHT - PAGE_MARGIN - cell_height * ((i / columns) as f32 + 0.5) + caption_size,
+        );
+        for shape in sorted_shapes(sudoku, theme, size, show_notes) {
+            match shape {
+                Shape::Rect { center, size, color, .. } => {
+                    let corner = origin + center - size / 2.0;
+                    let color = flatten(color, theme.tile_color);
+                    writeln!(content, "{} rg {:.2} {:.2} {:.2} {:.2} re f", pdf_color(color), corner.x, corner.y, size.x, size.y).unwrap();
+                }
+                Shape::Line { start, end, weight, color, .. } => {
+                    let (start, end) = (origin + start, origin + end);
+                    writeln!(content, "{} RG {} w 2 J {:.2} {:.2} m {:.2} {:.2} l S", pdf_color(color.color), weight, start.x, start.y, end.x, end.y).unwrap();
+                }
+                Shape::Text { center, font_size, text, color, .. } => {
+                    let font_size = nannou::text::pt_to_px(font_size);
+                    let center = origin + center;
+                    pdf_text(&mut content, &text, center.x, center.y - font_size * 0.35, font_size, color.color);
+                }
+            }
+        }
+        let baseline = origin.y - size / 2.0 - caption_size * 1.5;
+        pdf_text(&mut content, caption, origin.x, baseline, caption_size, theme.secondary_color);
+    }
+    content
+}
+
+/// Writes the puzzles with their captions into a PDF with `per_page` puzzles on each A4 page.
+pub fn pdf(puzzles: &[(String, Sudoku)], theme: &Theme, show_notes: bool, per_page: usize) -> Vec<u8> {
+    let (columns, rows) = page_grid(per_page);
+    let pages: Vec<String> = puzzles
+        .chunks(columns * rows)
+        .map(|page| pdf_page(page, theme, show_notes, per_page))
+        .collect();
+
+    // Objects 1 to 3 are the catalog, the page tree and the font, followed by a page and its content for every page.
+    let kids: Vec<String> = (0..pages.len()).map(|i| format!("{} 0 R", 4 + 2 * i)).collect();
+    let mut objects = vec![
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids.join(" "), pages.len()),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>".to_string(),
+    ];
+    for (i, content) in pages.iter().enumerate() {
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>",
+            PAGE_WIDTH, PAGE_HEIGHT, 5 + 2 * i
+        ));
+        objects.push(format!("<< /Length {} >>\nstream\n{}endstream", content.len(), content));
+    }
+
+    let mut pdf = String::from("%PDF-1.4\n");
+    let mut offsets = Vec::new();
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        write!(pdf, "{} 0 obj\n{}\nendobj\n", i + 1, object).unwrap();
+    }
+    let xref = pdf.len();
+    write!(pdf, "xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).unwrap();
+    for offset in offsets {
+        writeln!(pdf, "{:010} 00000 n ", offset).unwrap();
+    }
+    write!(pdf, "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n", objects.len() + 1, xref).unwrap();
+    pdf.into_bytes()
+}
+
+/// Exports the board as an SVG, a single page PDF, a DIMACS CNF formula and a .sdm line into a timestamped file below `exports`.
+/// Returns the path of the SVG file.
+pub fn export_board(sudoku: &Sudoku, theme: &Theme, givens_only: bool, show_notes: bool) -> Result<PathBuf, String> {
+    let printable = printable(sudoku, givens_only);
+    let path = storage::timestamped_path(Path::new(EXPORTS_DIRECTORY));
+    std::fs::create_dir_all(EXPORTS_DIRECTORY).map_err(|e| e.to_string())?;
+    std::fs::write(path.with_extension("pdf"), pdf(&[(String::new(), printable.clone())], theme, show_notes, 1))
+        .map_err(|e| e.to_string())?;
+    std::fs::write(path.with_extension("cnf"), sat::encode(&printable.tiles, &dlx::standard_houses()).to_dimacs())
+        .map_err(|e| e.to_string())?;
+    std::fs::write(path.with_extension("sdm"), printable.to_line() + "\n").map_err(|e| e.to_string())?;
+    let svg_path = path.with_extension("svg");
+    std::fs::write(&svg_path, svg(&printable, theme, 600.0, show_notes)).map_err(|e| e.to_string())?;
+    Ok(svg_path)
+}
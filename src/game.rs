@@ -0,0 +1,117 @@
+use std::time::{Duration, Instant};
+
+use crate::daily::Day;
+use crate::records;
+use crate::sudoku::Sudoku;
+
+/// A puzzle played by hand. The timer starts with the first digit the player enters and
+/// stops once the puzzle is completed.
+pub struct Game {
+    /// Givens of the puzzle, a new game starts when they change.
+    pub puzzle: String,
+    /// Statistics category, the difficulty of bundled puzzles or "Custom".
+    pub category: &'static str,
+    /// Day of the daily puzzle, if the game is one.
+    pub daily: Option<Day>,
+    pub hints: u32,
+    pub moves: u32,
+    pub finished: bool,
+    pub paused: bool,
+    elapsed: Duration,
+    /// Start of the current stretch of play, `None` while the timer is stopped.
+    resumed: Option<Instant>,
+}
+
+impl Game {
+    pub fn new(sudoku: &Sudoku) -> Self {
+        Game {
+            puzzle: sudoku.givens_line(),
+            category: sudoku.id.map_or(records::CUSTOM, |id| id.difficulty.to_string()),
+            daily: None,
+            hints: 0,
+            moves: 0,
+            finished: false,
+            paused: false,
+            elapsed: Duration::ZERO,
+            resumed: None,
+        }
+    }
+
+    /// True once the player entered a digit.
+    pub fn is_started(&self) -> bool {
+        self.moves > 0
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed + self.resumed.map_or(Duration::ZERO, |resumed| resumed.elapsed())
+    }
+
+    /// Counts an entry of the player and starts the timer on the first one.
+    pub fn record_move(&mut self) {
+        self.moves += 1;
+        if !self.finished && !self.paused && self.resumed.is_none() {
+            self.resumed = Some(Instant::now());
+        }
+    }
+
+    pub fn toggle_pause(&mut self) {
+        if self.finished || !self.is_started() {
+            return;
+        }
+        self.paused = !self.paused;
+        match self.resumed.take() {
+            Some(resumed) => self.elapsed += resumed.elapsed(),
+            None => self.resumed = Some(Instant::now()),
+        }
+    }
+
+    pub fn finish(&mut self) {
+        self.elapsed = self.elapsed();
+        self.resumed = None;
+        self.finished = true;
+    }
+}
+
+/// Duration as minutes and seconds, with hours once needed.
+pub fn format_time(time: Duration) -> String {
+    let seconds = time.as_secs();
+    match seconds / 3600 {
+        0 => format!("{}:{:02}", seconds / 60, seconds % 60),
+        hours => format!("{}:{:02}:{:02}", hours, seconds / 60 % 60, seconds % 60),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_time() {
+        assert_eq!(format_time(Duration::from_secs(0)), "0:00");
+        assert_eq!(format_time(Duration::from_secs(754)), "12:34");
+        assert_eq!(format_time(Duration::from_secs(3 * 3600 + 65)), "3:01:05");
+    }
+
+    #[test]
+    fn timer_runs_between_first_move_and_finish() {
+        let mut game = Game::new(&Sudoku::default());
+        game.toggle_pause();
+        assert!(!game.paused, "a game that has not started cannot be paused");
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(game.elapsed(), Duration::ZERO);
+
+        game.record_move();
+        std::thread::sleep(Duration::from_millis(20));
+        game.toggle_pause();
+        let paused = game.elapsed();
+        assert!(paused >= Duration::from_millis(20));
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(game.elapsed(), paused);
+
+        game.toggle_pause();
+        game.finish();
+        let finished = game.elapsed();
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(game.elapsed(), finished);
+    }
+}
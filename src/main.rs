@@ -1,41 +1,106 @@
 // #![windows_subsystem = "windows"]
+use std::sync::OnceLock;
+
 use nannou::prelude::*;
 
+mod batch;
+mod bench;
+mod bindings;
+mod bitboard;
+mod board;
+mod browser;
+mod cli;
+mod config;
+mod controls;
+mod daily;
+mod dlx;
 mod events;
+mod export;
+mod game;
 mod model;
+mod race;
+mod recorder;
+mod records;
+mod render;
+mod sat;
+mod solver;
+mod stochastic;
+mod storage;
 mod sudoku;
 use sudoku::SolverState;
 mod theme;
+mod validate;
 use model::Model;
 
+/// Options of the visualizer, the model function of nannou takes no arguments.
+static LAUNCH: OnceLock<cli::Launch> = OnceLock::new();
+
 fn main() {
-    nannou::app(model).update(update).run();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !cli::is_launch(&args) {
+        if let Err(error) = cli::run(&args) {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+        return;
+    }
+    match cli::launch(&args) {
+        Ok(launch) => _ = LAUNCH.set(launch),
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    }
+    nannou::app(model).update(update).exit(exit).run();
 }
 
 fn model(app: &App) -> Model {
-    let width = 1000;
-    let height = 800;
+    // Escape is the default key of the quit action, which can be bound to other keys.
+    app.set_exit_on_escape(false);
+    let launch = LAUNCH.get().unwrap();
+    let config = config::Config::load();
+    let settings = launch.settings(&config);
     app.new_window()
-        .size(width, height)
+        .size(settings.width, settings.height)
         .mouse_wheel(events::handle_mouse_wheel_events)
         .mouse_moved(events::mouse_moved)
+        .mouse_pressed(events::mouse_pressed)
+        .dropped_file(events::dropped_file)
         .key_pressed(events::handle_key_pressed)
         .view(view)
         .build()
         .unwrap();
 
-    Model::new(width, height)
+    Model::new(config, launch)
 }
 
 fn update(app: &App, model: &mut Model, _update: Update) {
     model.update_past_frametimes(app.duration.since_prev_update.as_secs_f32());
     events::handle_continious_key_inputs(app, model);
-    events::handle_mouse_button_events(app, model.window_height, model.window_width, model);
+    events::handle_mouse_button_events(app, model);
 
     model.update_size(app.window_rect().w() as u32, app.window_rect().h() as u32);
-    if let SolverState::Running = model.sudoku.state {
+    model.mouse = app.mouse.position();
+    model.mouse_down = app.mouse.buttons.left().is_down();
+    model.update_game();
+    model.update_settings(app.main_window().is_fullscreen());
+    model.reload_themes();
+    if let Some(browser) = model.browser.as_mut() {
+        browser.update(&model.records);
+    }
+    if let Some(race) = model.race.as_mut() {
+        let steps = model.sudoku.get_steps();
+        race.step(steps);
+    } else if let SolverState::Running = model.sudoku.state {
         model.sudoku.step();
     }
+    if let Some(path) = model.recorder.next_frame_path() {
+        app.main_window().capture_frame(path);
+    }
+}
+
+fn exit(_app: &App, mut model: Model) {
+    model.save_settings();
 }
 
 fn view(app: &App, model: &Model, frame: Frame) {
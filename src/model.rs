@@ -1,18 +1,131 @@
 use std::collections::VecDeque;
-use nannou::{color::{self, rgb::Rgba}, prelude::*};
+use std::time::{Duration, Instant};
+use nannou::{color::{self, rgb::Rgba}, draw::primitive::text::DrawingText, prelude::*};
 
-use crate::sudoku::{self, Sudoku, Tile};
-use crate::theme::{Theme, ThemeType};
+use crate::bindings::{Action, Bindings};
+use crate::board::{self, BoardOptions, Shape};
+use crate::browser::{self, Browser};
+use crate::cli::Launch;
+use crate::config::Config;
+use crate::controls::{self, Click, Control, ControlKind};
+use crate::daily::{Daily, Day};
+use crate::bitboard::Bitboard;
+use crate::export;
+use crate::game::{self, Game};
+use crate::race::Race;
+use crate::recorder::Recorder;
+use crate::records::{self, GameRecord, Records};
+use crate::solver::{Breakpoint, SolverKind};
+use crate::sudoku::{self, Difficulty, SolverState, Sudoku, Tile};
+use crate::theme::{Theme, Themes};
+
+/// Range of the font scale, large text still has to fit the panel and the browser rows.
+const MIN_FONT_SCALE: f32 = 0.75;
+const MAX_FONT_SCALE: f32 = 1.5;
+
+/// Height of the digit pad under the board, relative to the board.
+const PAD_HEIGHT: f32 = 1.0 / 11.0;
+
+/// Solver statistic shown as a per-cell heatmap over the grid.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Heatmap {
+    Off,
+    Visits,
+    Writes,
+}
+
+impl Heatmap {
+    pub fn next(self) -> Self {
+        match self {
+            Heatmap::Off => Heatmap::Visits,
+            Heatmap::Visits => Heatmap::Writes,
+            Heatmap::Writes => Heatmap::Off,
+        }
+    }
+
+    pub fn to_string(self) -> &'static str {
+        match self {
+            Heatmap::Off => "Off",
+            Heatmap::Visits => "Visits",
+            Heatmap::Writes => "Writes",
+        }
+    }
+}
+
+/// Kind of a line in the side panel, determines its font size and color.
+#[derive(Clone, Copy)]
+enum LabelKind {
+    Title,
+    Heading,
+    Value,
+    Hint,
+}
+
+impl LabelKind {
+    fn font_size(self, gui_width: f32) -> f32 {
+        match self {
+            LabelKind::Title => gui_width / 6.0,
+            LabelKind::Heading => gui_width / 8.0,
+            LabelKind::Value | LabelKind::Hint => gui_width / 15.0,
+        }
+    }
+}
+
+/// Line of the side panel.
+enum PanelItem {
+    Label(LabelKind, String),
+    /// Row of controls sharing the width of the panel.
+    Controls(Vec<Control>),
+}
+
+impl PanelItem {
+    fn height(&self, gui_width: f32) -> f32 {
+        match self {
+            PanelItem::Label(kind, _) => kind.font_size(gui_width) * 1.5,
+            PanelItem::Controls(_) => LabelKind::Value.font_size(gui_width) * 2.0,
+        }
+    }
+}
 
 pub struct Model {
     pub sudoku: sudoku::Sudoku,
+    /// Difficulty of the random puzzles. Loaded puzzles keep their own difficulty in
+    /// `sudoku`, only the difficulty selector changes this one.
+    pub difficulty: Difficulty,
     pub theme: Theme,
+    pub themes: Themes,
     pub window_width: u32,
     pub window_height: u32,
     pub offset: f32,
     pub selected: Option<usize>,
     pub show_available: bool,
     pub higlight_relevant: bool,
+    pub shape_cues: bool,
+    pub digit_first: bool,
+    /// Digit picked on the digit pad in digit first mode, 0 for erasing.
+    pub pad_digit: Option<u8>,
+    /// Size of all text relative to the default, see `change_font_scale`.
+    pub font_scale: f32,
+    pub heatmap: Heatmap,
+    pub recorder: Recorder,
+    pub export_status: String,
+    pub race: Option<Race>,
+    pub browser: Option<Browser>,
+    pub records: Records,
+    pub game: Game,
+    pub show_stats: bool,
+    pub daily: Daily,
+    /// Selected day while the calendar of daily puzzles is shown.
+    pub calendar: Option<Day>,
+    pub bindings: Bindings,
+    /// Position of the mouse and whether the left button is down, for the hover and
+    /// press states of the controls.
+    pub mouse: Point2,
+    pub mouse_down: bool,
+    /// Settings as saved in the config file and as they were last seen, to find changes.
+    config: Config,
+    settings: Config,
+    settings_changed: Option<Instant>,
     size: f32,
     gui_width: f32,
     past_frametimes: VecDeque<f32>,
@@ -23,12 +136,34 @@ pub struct Model {
 
 impl Default for Model {
     fn default() -> Self {
-        let mut model = Model {
+        Model {
             show_available: true,
             higlight_relevant: true,
+            shape_cues: false,
+            digit_first: false,
+            pad_digit: None,
+            font_scale: 1.0,
+            heatmap: Heatmap::Off,
+            recorder: Recorder::default(),
+            export_status: "-".to_string(),
+            race: None,
+            browser: None,
+            records: Records::default(),
+            game: Game::new(&Sudoku::default()),
+            show_stats: false,
+            daily: Daily::default(),
+            calendar: None,
+            bindings: Bindings::default(),
+            mouse: Point2::ZERO,
+            mouse_down: false,
+            config: Config::default(),
+            settings: Config::default(),
+            settings_changed: None,
             selected: None,
             sudoku: sudoku::Sudoku::default(),
+            difficulty: Difficulty::Medium,
             theme: Theme::default(),
+            themes: Themes::default(),
             window_width: 0,
             window_height: 0,
             offset: 0.0,
@@ -38,30 +173,189 @@ impl Default for Model {
             past_frametimes_sum: 0.0,
             fps: 0.0,
             application_ticks: 0,
-        };
-        model.sudoku.load_random();
-        model
+        }
     }
 }
 
 impl Model {
-    pub fn new(width: u32, height: u32) -> Self {
-        let mut model = Model::default();
-        model.update_size(width, height);
-        model.theme.update(ThemeType::Dark);
+    pub fn new(config: Config, launch: &Launch) -> Self {
+        let settings = launch.settings(&config);
+        let mut model = Model {
+            records: Records::load(),
+            daily: Daily::load(),
+            bindings: Bindings::load(),
+            show_available: settings.show_available,
+            higlight_relevant: settings.highlight_relevant,
+            shape_cues: settings.shape_cues,
+            digit_first: settings.digit_first,
+            font_scale: settings.font_scale.clamp(MIN_FONT_SCALE, MAX_FONT_SCALE),
+            themes: Themes::load(),
+            config,
+            settings: settings.clone(),
+            ..Model::default()
+        };
+        model.sudoku.reseed(settings.seed.unwrap_or_else(rand::random));
+        model.difficulty = settings.difficulty;
+        model.sudoku.set_steps_per_frame(settings.steps_per_frame);
+        match &launch.puzzle {
+            Some(puzzle) => {
+                model.sudoku.tiles = puzzle.tiles;
+                model.sudoku.id = puzzle.id;
+                // Only puzzle IDs name a difficulty, a line of digits keeps the selected one.
+                model.sudoku.difficulty = puzzle.id.map_or(model.difficulty, |id| id.difficulty);
+            }
+            None => model.load_random(),
+        }
+        model.game = Game::new(&model.sudoku);
+        if launch.daily {
+            model.play_daily(Day::today());
+        }
+        model.update_size(settings.width, settings.height);
+        model.theme = match model.themes.find(&settings.theme) {
+            Ok(theme) => theme,
+            Err(error) => {
+                eprintln!("{}", error);
+                Theme::default()
+            }
+        };
+        model.settings = model.current_settings();
         model
     }
 
+    fn current_settings(&self) -> Config {
+        Config {
+            width: self.window_width,
+            height: self.window_height,
+            theme: self.theme.name.clone(),
+            difficulty: self.difficulty,
+            steps_per_frame: self.sudoku.steps_per_frame(),
+            show_available: self.show_available,
+            highlight_relevant: self.higlight_relevant,
+            shape_cues: self.shape_cues,
+            font_scale: self.font_scale,
+            digit_first: self.digit_first,
+            seed: self.settings.seed,
+        }
+    }
+
+    /// Saves the settings the player changed. The file is written a second after the last
+    /// change, so resizing the window or holding a speed key writes it once. The size of a
+    /// fullscreen window is not saved.
+    pub fn update_settings(&mut self, fullscreen: bool) {
+        let mut current = self.current_settings();
+        if fullscreen {
+            (current.width, current.height) = (self.settings.width, self.settings.height);
+        }
+        if current != self.settings {
+            self.config.take_changes(&self.settings, &current);
+            self.settings = current;
+            self.settings_changed = Some(Instant::now());
+        }
+        if self.settings_changed.is_some_and(|changed| changed.elapsed() > Duration::from_secs(1)) {
+            self.save_settings();
+        }
+    }
+
+    /// Switches to the theme `steps` after the current one, backwards if negative.
+    pub fn change_theme(&mut self, steps: isize) {
+        self.theme = self.themes.step(&self.theme.name, steps).clone();
+    }
+
+    /// Loads a random puzzle of the selected difficulty.
+    pub fn load_random(&mut self) {
+        self.sudoku.difficulty = self.difficulty;
+        self.sudoku.load_random();
+    }
+
+    /// Loads a random puzzle of another difficulty.
+    pub fn set_difficulty(&mut self, difficulty: Difficulty) {
+        if difficulty != self.difficulty {
+            self.difficulty = difficulty;
+            self.load_random();
+        }
+    }
+
+    /// Makes all text a quarter of the default size larger or smaller per step.
+    pub fn change_font_scale(&mut self, steps: f32) {
+        self.font_scale = (self.font_scale + steps * 0.25).clamp(MIN_FONT_SCALE, MAX_FONT_SCALE);
+    }
+
+    /// Applies changes of the theme files to the current theme.
+    pub fn reload_themes(&mut self) {
+        if self.themes.reload_if_changed() {
+            if let Some(theme) = self.themes.get(&self.theme.name) {
+                self.theme = theme.clone();
+            }
+        }
+    }
+
+    /// Writes changed settings that were not saved yet.
+    pub fn save_settings(&mut self) {
+        if self.settings_changed.take().is_some() {
+            if let Err(error) = self.config.save() {
+                eprintln!("could not save the settings: {}", error);
+            }
+        }
+    }
+
     pub fn draw(&self, draw: &Draw) {
         draw.background().color(self.theme.background_color);
-        self.draw_grid(draw);
-        self.draw_numbers(draw);
+        // The board and the views in its place sit above the digit pad.
+        let board = draw.y(self.board_shift());
+        if let Some(race) = &self.race {
+            self.draw_race(&board, race);
+            self.draw_gui(draw);
+            return;
+        }
+        if let Some(browser) = &self.browser {
+            self.draw_browser(&board, browser);
+            self.draw_gui(draw);
+            return;
+        }
+        if let Some(day) = self.calendar {
+            self.draw_calendar(&board, day);
+            self.draw_gui(draw);
+            return;
+        }
+        if self.show_stats {
+            self.draw_stats(&board);
+            self.draw_gui(draw);
+            return;
+        }
+        if self.game.paused {
+            self.draw_pause(&board);
+            self.draw_gui(draw);
+            return;
+        }
+        self.draw_board(&board);
+        self.draw_selection(&board);
+        self.draw_heatmap(&board);
+        self.draw_breakpoints(&board);
+        self.draw_pad(draw);
         self.draw_gui(draw);
+        if self.shows_timeline() {
+            self.draw_timeline(draw);
+        }
+    }
+
+    /// Distance the board is moved up to make room for the digit pad.
+    fn board_shift(&self) -> f32 {
+        self.size * PAD_HEIGHT / 2.0
+    }
+
+    /// Tile of the board under the point.
+    pub fn tile_at(&self, point: Point2) -> Option<usize> {
+        let x = point.x + self.offset + self.size / 2.0;
+        let y = point.y - self.board_shift() + self.size / 2.0;
+        if !(0.0..self.size).contains(&x) || !(0.0..self.size).contains(&y) {
+            return None;
+        }
+        Some((y / (self.size / 9.0)) as usize * 9 + (x / (self.size / 9.0)) as usize)
     }
 
     pub fn update_size(&mut self, width: u32, height: u32) {
         self.application_ticks += 1;
-        let size = height.min(width) as f32 - 10.0;
+        let size = ((height as f32 - 10.0) / (1.0 + PAD_HEIGHT)).min(width as f32 - 10.0);
         let gui_width = ((width as f32 - self.size) - 10.0)
             .min(320.0)
             .min(self.size / 2.7)
@@ -79,14 +373,231 @@ impl Model {
     }
 
     pub fn try_write_tile(&mut self, tile: Tile) {
-        if self.sudoku.is_running() {
+        if self.sudoku.is_active() || self.race.is_some() || self.show_stats || self.game.paused || self.game.finished {
             return;
         }
         self.sudoku.clear_variables();
         self.sudoku.reset_solver();
         if let Some(selected) = self.selected {
+            let before = self.sudoku.tiles[selected];
             self.sudoku.try_insert(selected, tile);
+            if self.sudoku.tiles[selected] != before {
+                self.game.record_move();
+                self.check_completed();
+            }
+        }
+    }
+
+    /// Moves the selection by whole tiles, wrapping around the edges of the grid. Selects
+    /// the top left tile if nothing is selected.
+    pub fn move_selection(&mut self, columns: isize, rows: isize) {
+        self.selected = Some(match self.selected {
+            Some(indx) => {
+                let column = (indx % 9) as isize + columns;
+                let row = (indx / 9) as isize + rows;
+                (row.rem_euclid(9) * 9 + column.rem_euclid(9)) as usize
+            }
+            None => 72,
+        });
+    }
+
+    /// Selects the next empty tile in reading order after the selected one, from the top
+    /// left if nothing is selected. Keeps the selection if no tile is empty.
+    pub fn select_next_empty(&mut self, backwards: bool) {
+        // Rows are stored from the bottom up, reading goes from the top down.
+        let reading = |indx: usize| (8 - indx / 9) * 9 + indx % 9;
+        let start = self.selected.map_or(if backwards { 0 } else { 80 }, reading);
+        let next = (1..=81)
+            .map(|step| match backwards {
+                true => (start + 81 - step) % 81,
+                false => (start + step) % 81,
+            })
+            .map(reading)
+            .find(|indx| self.sudoku.tiles[*indx] == Tile::Empty);
+        if next.is_some() {
+            self.selected = next;
+        }
+    }
+
+    pub fn toggle_digit_first(&mut self) {
+        self.digit_first = !self.digit_first;
+        self.pad_digit = None;
+    }
+
+    /// Enters a digit of the digit pad into the selected tile, 0 erases it. In digit first
+    /// mode the digit is picked instead, or put back if it was picked already.
+    pub fn press_pad(&mut self, digit: u8) {
+        if self.digit_first {
+            self.pad_digit = if self.pad_digit == Some(digit) { None } else { Some(digit) };
+        } else if digit == 0 {
+            self.try_write_tile(Tile::Empty);
+        } else {
+            self.try_write_tile(Tile::PlayerVariable(digit));
+        }
+    }
+
+    /// Puts the digit picked on the pad into the selected tile, a tile holding it already
+    /// is erased. Returns false if no digit is picked.
+    pub fn place_pad_digit(&mut self) -> bool {
+        let Some(digit) = self.pad_digit.filter(|_| self.digit_first) else {
+            return false;
+        };
+        let tile = match self.selected.map(|indx| self.sudoku.tiles[indx]) {
+            _ if digit == 0 => Tile::Empty,
+            Some(Tile::PlayerVariable(n)) if n == digit => Tile::Empty,
+            _ => Tile::PlayerVariable(digit),
+        };
+        self.try_write_tile(tile);
+        true
+    }
+
+    /// Fills a tile with the digit of the solution: the selected tile if it is empty or
+    /// wrong, otherwise the first such tile.
+    pub fn hint(&mut self) {
+        if self.sudoku.is_active() || self.race.is_some() || self.game.paused || self.game.finished {
+            return;
+        }
+        let givens = self.sudoku.tiles.map(|tile| match tile {
+            Tile::Const(_) => tile,
+            _ => Tile::Empty,
+        });
+        let Some(solution) = Bitboard::new(&givens).and_then(|mut board| board.solve()) else {
+            return;
+        };
+        self.sudoku.clear_variables();
+        self.sudoku.reset_solver();
+        let is_missing = |indx: usize| self.sudoku.tiles[indx] != Tile::Const(solution[indx]) && self.sudoku.tiles[indx] != Tile::PlayerVariable(solution[indx]);
+        let Some(indx) = self.selected.filter(|indx| is_missing(*indx)).or_else(|| (0..81).find(|indx| is_missing(*indx))) else {
+            return;
+        };
+        self.sudoku.tiles[indx] = Tile::PlayerVariable(solution[indx]);
+        self.game.hints += 1;
+        self.game.record_move();
+        self.check_completed();
+    }
+
+    fn check_completed(&mut self) {
+        if self.game.finished || !self.sudoku.is_completed() {
+            return;
+        }
+        self.game.finish();
+        self.sudoku.state = SolverState::SolutionFound;
+        self.save_game();
+        if let Some(day) = self.game.daily {
+            if let Err(error) = self.daily.complete(day, self.game.elapsed(), self.game.hints) {
+                eprintln!("could not save the daily puzzle: {}", error);
+            }
+        }
+    }
+
+    fn save_game(&mut self) {
+        // A grid without givens is drawn by the player, not a puzzle.
+        if !self.game.puzzle.bytes().any(|digit| digit != b'0') {
+            return;
         }
+        let record = GameRecord {
+            category: self.game.category.to_string(),
+            solved: self.game.finished,
+            time: self.game.elapsed(),
+            hints: self.game.hints,
+            puzzle: self.game.puzzle.clone(),
+        };
+        if let Err(error) = self.records.add_game(record) {
+            eprintln!("could not save the game: {}", error);
+        }
+    }
+
+    /// Starts a new game once another puzzle was loaded, or once the grid of a finished game
+    /// was cleared. A game the player started but did not finish is saved as unsolved.
+    pub fn update_game(&mut self) {
+        let cleared = self.game.finished && !self.sudoku.is_completed();
+        if self.sudoku.givens_line() == self.game.puzzle && !cleared {
+            return;
+        }
+        if self.game.is_started() && !self.game.finished {
+            self.save_game();
+        }
+        self.game = Game::new(&self.sudoku);
+    }
+
+    /// Opens the calendar of daily puzzles on today, or closes it.
+    pub fn toggle_calendar(&mut self) {
+        self.calendar = match self.calendar {
+            Some(_) => None,
+            None => Some(Day::today()),
+        };
+    }
+
+    /// Loads the daily puzzle of a day up to today and closes the calendar. The daily that
+    /// is being played is continued, not loaded again.
+    pub fn play_daily(&mut self, day: Day) {
+        if day > Day::today() {
+            return;
+        }
+        self.calendar = None;
+        if self.game.daily == Some(day) && !self.game.finished {
+            return;
+        }
+        self.sudoku.load_id(day.puzzle());
+        self.update_game();
+        if self.game.finished {
+            self.game = Game::new(&self.sudoku);
+        }
+        self.game.daily = Some(day);
+    }
+
+    /// Exports the board as shown, or only its givens, as SVG, PDF, CNF and SDM.
+    pub fn export(&mut self, givens_only: bool) {
+        self.export_status = match export::export_board(&self.sudoku, &self.theme, givens_only, self.show_available) {
+            Ok(path) => format!("Saved {}", path.with_extension("").display()),
+            Err(error) => format!("Failed: {}", error),
+        };
+    }
+
+    /// Starts a race of all solvers on the current puzzle, or ends the running race.
+    pub fn toggle_race(&mut self) {
+        self.race = match self.race {
+            Some(_) => None,
+            None => {
+                let kinds = [SolverKind::Backtracking, SolverKind::DancingLinks, SolverKind::Annealing, SolverKind::Genetic];
+                Some(Race::new(&self.sudoku, &kinds))
+            }
+        };
+    }
+
+    /// Opens the puzzle browser on the current puzzle, or closes it.
+    pub fn toggle_browser(&mut self) {
+        self.browser = match self.browser {
+            Some(_) => None,
+            None => Some(Browser::new(&self.sudoku)),
+        };
+    }
+
+    /// Loads the puzzle selected in the browser and closes it.
+    pub fn open_selected_puzzle(&mut self) {
+        let Some((puzzle, id)) = self.browser.as_ref().and_then(|browser| browser.selected()) else {
+            return;
+        };
+        match id {
+            Some(id) => self.sudoku.load_id(id),
+            None => self.sudoku.load_line(puzzle),
+        }
+        self.browser = None;
+    }
+
+    /// Imports a .sdm file as a collection and shows it in the browser.
+    pub fn import(&mut self, path: &std::path::Path) {
+        if self.race.is_some() || self.sudoku.is_active() {
+            return;
+        }
+        let browser = self.browser.get_or_insert_with(|| Browser::new(&self.sudoku));
+        if let Err(error) = browser.import(path) {
+            browser.message = format!("Import failed: {}", error);
+        }
+    }
+
+    pub fn fps(&self) -> f32 {
+        self.fps
     }
 
     pub fn update_past_frametimes(&mut self, time: f32) {
@@ -96,127 +607,330 @@ impl Model {
         if self.past_frametimes.len() > 150 {
             self.past_frametimes_sum -= self.past_frametimes.pop_front().unwrap();
         }
-        if self.application_ticks % 150 == 0 {
+        if self.application_ticks.is_multiple_of(150) {
             self.fps = (self.past_frametimes.len() as f32 / self.past_frametimes_sum).round();
         }
     }
 
-    fn draw_numbers(&self, draw: &Draw) {
-        self.sudoku.tiles.iter().enumerate().for_each(|(i, t)| {
-            let x = self.size / 9.0 * ((i % 9) as f32 + 0.5) - self.size / 2.0 - self.offset;
-            let y = self.size / 9.0 * ((i / 9) as f32 + 0.5) - self.size / 2.04;
-            match t {
-                Tile::Empty if self.show_available => {
-                    let avaliable = self.sudoku.avaliable_numbers(i);
-                    for n in 0..9 {
-                        if avaliable >> n & 2 == 0 {
-                            draw.text(&format!("{}", n + 1))
-                                .x_y(x + (n % 3 - 1) as f32 * self.size / 40.0, y + (n / 3 - 1) as f32 * self.size / 40.0 - self.size / 200.0)
-                                .z(4.0)
-                                .font_size(self.size as u32 / 40)
-                                .color(self.theme.secondary_color);
-                        }
-                    }
-                },
-                Tile::SolverVariable(n) => {
-                    draw.text(&n.to_string())
-                        .x_y(x, y)
-                        .z(4.0)
-                        .font_size(self.size as u32 / 16)
-                        .color(self.theme.secondary_color);
-                },
-                Tile::Const(n) => {
-                    let rect_x = self.size / 9.0 * ((i % 9) as f32 + 0.5) - self.size / 2.0 - self.offset;
-                    let rect_y = self.size / 9.0 * ((i / 9) as f32 + 0.5) - self.size / 2.0;
+    fn draw_board(&self, draw: &Draw) {
+        let options = BoardOptions {
+            show_available: self.show_available,
+            show_active: true,
+            shape_cues: self.shape_cues,
+            font_scale: self.font_scale,
+        };
+        let shapes = board::shapes(&self.sudoku, &self.theme, self.size, options);
+        self.draw_shapes(draw, shapes, vec2(-self.offset, 0.0));
+    }
+
+    /// Draws the shapes of a board centered on the given point.
+    fn draw_shapes(&self, draw: &Draw, shapes: Vec<Shape>, origin: Vec2) {
+        for shape in shapes {
+            match shape {
+                Shape::Rect { center, size, z, color } => {
                     draw.rect()
-                        .x_y(rect_x, rect_y)
-                        .z(1.1)
-                        .w_h(self.size / 9.0, self.size / 9.0)
-                        .color(color::Rgba {
-                            color: self.theme.secondary_color,
-                            alpha: self.theme.theme_alpha,
-                        
-                        });
-                    draw.text(&n.to_string())
-                        .x_y(x, y)
-                        .z(4.0)
-                        .font_size(self.size as u32 / 16)
-                        .color(self.theme.primary_color);
-                },
-                Tile::PlayerVariable(n) => {
-                    draw.text(&n.to_string())
-                        .x_y(x, y)
-                        .z(4.0)
-                        .font_size(self.size as u32 / 16)
-                        .color(self.theme.primary_color);
-                },
-                _ => (),
+                        .xy(origin + center)
+                        .z(z)
+                        .wh(size)
+                        .color(color);
+                }
+                Shape::Line { start, end, weight, z, color } => {
+                    draw.line()
+                        .start(origin + start)
+                        .end(origin + end)
+                        .z(z)
+                        .stroke_weight(weight)
+                        .color(color);
+                }
+                Shape::Text { center, font_size, text, z, color } => {
+                    self.text(draw, &text)
+                        .xy(origin + center)
+                        .z(z)
+                        .font_size(font_size)
+                        .color(color);
+                }
             }
-        });
+        }
+    }
+
+    /// Draws the boards of the racing solvers in a grid, each with its name, steps and time.
+    fn draw_race(&self, draw: &Draw, race: &Race) {
+        let columns = (race.racers.len() as f32).sqrt().ceil() as usize;
+        let rows = race.racers.len().div_ceil(columns);
+        let cell = self.size / columns.max(rows) as f32;
+        let board_size = cell * 0.85;
+        let options = BoardOptions {
+            show_available: false,
+            show_active: true,
+            shape_cues: self.shape_cues,
+            font_scale: 1.0,
+        };
+        for (i, racer) in race.racers.iter().enumerate() {
+            let origin = vec2(
+                -self.offset - self.size / 2.0 + cell * ((i % columns) as f32 + 0.5),
+                self.size / 2.0 - cell * ((i / columns) as f32 + 0.5) - cell * 0.04,
+            );
+            self.draw_shapes(draw, board::shapes(&racer.sudoku, &self.theme, board_size, options), origin);
+            let place = match racer.place {
+                Some(place) => format!("#{} {}", place, racer.sudoku.state.to_string()),
+                None if racer.is_finished() => racer.sudoku.state.to_string().to_owned(),
+                None if race.paused => "Paused".to_string(),
+                None => "Running".to_string(),
+            };
+            let caption = format!(
+                "{}: {}  {} steps  {:.1?}",
+                racer.sudoku.solver.to_string(),
+                place,
+                racer.sudoku.stats().steps,
+                racer.elapsed
+            );
+            self.text(draw, &caption)
+                .xy(origin + vec2(0.0, board_size / 2.0 + cell * 0.04))
+                .w(cell)
+                .font_size(self.font_size(cell / 28.0))
+                .color(self.theme.primary_color);
+        }
     }
 
-    fn draw_grid(&self, draw: &Draw) {
+    /// Height of a line of the browser. The list is framed by a title, the filters, a message
+    /// and the column names above and the page number below.
+    fn browser_line_height(&self) -> f32 {
+        self.size / (browser::PAGE + 6) as f32
+    }
+
+    /// Row of the browser list under the point.
+    pub fn browser_row_at(&self, point: Point2) -> Option<usize> {
+        let point = point - vec2(0.0, self.board_shift());
+        if (point.x + self.offset).abs() > self.size / 2.0 || point.y > self.size / 2.0 {
+            return None;
+        }
+        let line = ((self.size / 2.0 - point.y) / self.browser_line_height()) as usize;
+        let row = line.checked_sub(4)?;
+        let rows = self.browser.as_ref()?.rows.len();
+        (row < rows).then_some(row)
+    }
+
+    /// Draws the list of puzzles of the browser in place of the board.
+    fn draw_browser(&self, draw: &Draw, browser: &Browser) {
+        let height = self.browser_line_height();
+        let left = -self.offset - self.size / 2.0;
+        let font_size = self.font_size(height * 0.55);
+        let line_y = |line: usize| self.size / 2.0 - height * (line as f32 + 0.5);
+        let text = |text: &str, column: f32, width: f32, line: usize, color| {
+            self.text(draw, text)
+                .x_y(left + self.size * (column + width / 2.0), line_y(line))
+                .w(self.size * width)
+                .left_justify()
+                .font_size(font_size)
+                .color(color);
+        };
+        let primary = self.theme.primary_color;
+        let secondary = self.theme.secondary_color;
+
+        let collection = browser.current();
+        text(&format!("{}  ({} puzzles, collection {} of {})", collection.name, collection.len(), browser.collection + 1, browser.collections.len()), 0.0, 1.0, 0, primary);
+        text(&format!("Clues: {}   Rating: {}   Status: {}", browser.clues.to_string(), browser.rating.to_string(), browser.status.to_string()), 0.0, 1.0, 1, secondary);
+        let message = match browser.query.is_empty() {
+            true => browser.message.clone(),
+            false => format!("Go to line: {}_", browser.query),
+        };
+        text(&message, 0.0, 1.0, 2, secondary);
+
+        let columns = [("Line", 0.0), ("Clues", 0.25), ("Guesses", 0.45), ("Solved", 0.7)];
+        for (name, column) in columns {
+            text(name, column, 0.25, 3, secondary);
+        }
+        for (i, row) in browser.rows.iter().enumerate() {
+            let values = [row.line.to_string(), row.clues.to_string(), row.rating.to_string(), if row.solved { "yes" } else { "" }.to_string()];
+            for (value, (_, column)) in values.iter().zip(columns) {
+                text(value, column, 0.25, i + 4, primary);
+            }
+        }
+        if browser.cursor < browser.rows.len() {
+            draw.rect()
+                .x_y(-self.offset, line_y(browser.cursor + 4))
+                .w_h(self.size, height)
+                .color(Rgba {
+                    color: self.theme.highlight_color,
+                    alpha: self.theme.theme_alpha,
+                });
+        }
+
+        let footer = match browser.page_count() {
+            Some(_) if browser.rows.is_empty() => "No puzzles match the filters".to_string(),
+            Some(pages) => format!("Page {} of {}", browser.page + 1, pages),
+            None => format!("Page {}, searching...", browser.page + 1),
+        };
+        text(&footer, 0.0, 1.0, browser::PAGE + 5, secondary);
+    }
+
+    /// Covers the board while the game is paused, so the timer cannot be cheated.
+    fn draw_pause(&self, draw: &Draw) {
         draw.rect()
-            .x_y(0.0 - self.offset, 0.0)
-            .z(0.0)
+            .x_y(-self.offset, 0.0)
             .w_h(self.size, self.size)
             .color(self.theme.tile_color);
-        for i in 0..=9 {
-            let (color, z) = if i % 3 == 0 { 
-                (self.theme.primary_color, 2.0) 
-            } else { 
-                (self.theme.secondary_color, 1.0) 
-            };
-            let width = if i % 3 == 0 { 2.0 } else { 1.0 };
-            draw.line()
-                .start(pt2(
-                    -self.size / 2.0 - self.offset,
-                    self.size / 9.0 * i as f32 - self.size / 2.0,
-                ))
-                .end(pt2(
-                    self.size / 2.0 - self.offset,
-                    self.size / 9.0 * i as f32 - self.size / 2.0,
-                ))
-                .z(z)
-                .stroke_weight(width)
-                .color(color);
-            draw.line()
-                .start(pt2(
-                    self.size / 9.0 * i as f32 - self.size / 2.0 - self.offset,
-                    -self.size / 2.0,
-                ))
-                .end(pt2(
-                    self.size / 9.0 * i as f32 - self.size / 2.0 - self.offset,
-                    self.size / 2.0,
-                ))
-                .z(z)
-                .stroke_weight(width)
+        self.text(draw, &format!("Paused\n{}", self.bindings.hint(&[Action::PauseTimer], "Resume")))
+            .x_y(-self.offset, 0.0)
+            .w(self.size)
+            .font_size(self.font_size(self.size / 20.0))
+            .color(self.theme.primary_color);
+    }
+
+    /// Draws a table of the statistics of every category in place of the board.
+    fn draw_stats(&self, draw: &Draw) {
+        let height = self.size / 12.0;
+        let left = -self.offset - self.size / 2.0;
+        let font_size = self.font_size(height * 0.35);
+        let columns = [("", 0.0), ("Played", 0.22), ("Solved", 0.37), ("Best", 0.52), ("Average", 0.67), ("Hints", 0.84)];
+        let text = |values: [String; 6], line: usize, color| {
+            for (value, (_, column)) in values.iter().zip(columns) {
+                self.text(draw, value)
+                    .x_y(left + self.size * (column + 0.1), self.size / 2.0 - height * (line as f32 + 0.5))
+                    .w(self.size * 0.2)
+                    .left_justify()
+                    .font_size(font_size)
+                    .color(color);
+            }
+        };
+        text(["Statistics".to_string(), String::new(), String::new(), String::new(), String::new(), String::new()], 0, self.theme.primary_color);
+        text(columns.map(|(name, _)| name.to_string()), 2, self.theme.secondary_color);
+        for (i, category) in records::categories().enumerate() {
+            let stats = self.records.stats(category);
+            let time = |time: Option<std::time::Duration>| time.map_or("-".to_string(), game::format_time);
+            let values = [
+                category.to_string(),
+                stats.played.to_string(),
+                stats.solved.to_string(),
+                time(stats.best),
+                time(stats.average),
+                stats.hints.to_string(),
+            ];
+            text(values, i + 3, self.theme.primary_color);
+        }
+        self.text(draw, "Best and average time count the puzzles solved without hints.")
+            .x_y(-self.offset, self.size / 2.0 - height * 9.5)
+            .w(self.size)
+            .left_justify()
+            .font_size(font_size)
+            .color(self.theme.secondary_color);
+    }
+
+    /// Height of a line of the calendar, a week takes two lines.
+    fn calendar_line_height(&self) -> f32 {
+        self.size / 17.0
+    }
+
+    /// Day of the calendar under the point.
+    pub fn calendar_day_at(&self, point: Point2) -> Option<Day> {
+        let point = point - vec2(0.0, self.board_shift());
+        let first = self.calendar?.month_start();
+        let left = -self.offset - self.size / 2.0;
+        let x = point.x - left;
+        let y = self.size / 2.0 - point.y - self.calendar_line_height() * 3.0;
+        if !(0.0..self.size).contains(&x) || !(0.0..self.calendar_line_height() * 12.0).contains(&y) {
+            return None;
+        }
+        let cell = (y / (self.calendar_line_height() * 2.0)) as i64 * 7 + (x / (self.size / 7.0)) as i64;
+        let day = first.offset(cell - first.weekday() as i64);
+        (day.month_start() == first).then_some(day)
+    }
+
+    /// Draws the month of the selected day with the solved daily puzzles in place of the board.
+    fn draw_calendar(&self, draw: &Draw, selected: Day) {
+        let height = self.calendar_line_height();
+        let width = self.size / 7.0;
+        let left = -self.offset - self.size / 2.0;
+        let font_size = self.font_size(height * 0.5);
+        let top = self.size / 2.0;
+        let text = |text: &str, x: f32, y: f32, width: f32, color| {
+            self.text(draw, text)
+                .x_y(x, y)
+                .w(width)
+                .left_justify()
+                .font_size(font_size)
                 .color(color);
+        };
+        let primary = self.theme.primary_color;
+        let secondary = self.theme.secondary_color;
+        let today = Day::today();
+
+        const MONTHS: [&str; 12] = ["January", "February", "March", "April", "May", "June", "July", "August", "September", "October", "November", "December"];
+        let (year, month, _) = selected.date();
+        let title = format!("Daily puzzle  {} {}", MONTHS[month as usize - 1], year);
+        text(&title, -self.offset, top - height * 0.5, self.size, primary);
+        let streak = format!("Streak: {} days   Best: {} days", self.daily.streak(today), self.daily.best_streak());
+        text(&streak, -self.offset, top - height * 1.5, self.size, secondary);
+        for (i, name) in ["Mo", "Tu", "We", "Th", "Fr", "Sa", "Su"].iter().enumerate() {
+            text(name, left + width * (i as f32 + 0.5), top - height * 2.5, width * 0.8, secondary);
         }
 
-        if self.sudoku.is_running() {
-            let x = (self.sudoku.active_indx % 9) as f32 * self.size / 9.0 - self.size / 2.0;
-            let y = (self.sudoku.active_indx / 9) as f32 * self.size / 9.0 - self.size / 2.0;
-            draw.rect()
-                .x_y(x + self.size / 18.0 - self.offset, y + self.size / 18.0)
-                .w_h(self.size / 9.0, self.size / 9.0)
-                .z(1.0)
-                .color(color::rgba(255, 0, 0, self.theme.theme_alpha * 2));
+        let first = selected.month_start();
+        for day in (0..first.month_length()).map(|days| first.offset(days)) {
+            let cell = first.weekday() as i64 + day.0 - first.0;
+            let x = left + width * ((cell % 7) as f32 + 0.5);
+            let y = top - height * (3.0 + 2.0 * (cell / 7) as f32 + 1.0);
+            draw.rect().x_y(x, y).w_h(width - 4.0, height * 2.0 - 4.0).color(self.theme.tile_color);
+            if day == selected {
+                draw.rect().x_y(x, y).w_h(width - 4.0, height * 2.0 - 4.0).color(Rgba {
+                    color: self.theme.highlight_color,
+                    alpha: self.theme.theme_alpha,
+                });
+            }
+            if day == today {
+                draw.rect()
+                    .x_y(x, y)
+                    .w_h(width - 4.0, height * 2.0 - 4.0)
+                    .no_fill()
+                    .stroke(primary)
+                    .stroke_weight(2.0);
+            }
+            let number_color = if day > today { secondary } else { primary };
+            text(&day.date().2.to_string(), x, y + height * 0.45, width * 0.8, number_color);
+            if let Some(record) = self.daily.get(day) {
+                let color = if record.completed == day { primary } else { secondary };
+                text(&game::format_time(record.time), x, y - height * 0.45, width * 0.8, color);
+            }
+        }
+
+        let status = match self.daily.get(selected) {
+            _ if selected > today => "not available yet".to_string(),
+            Some(record) => {
+                let late = if record.completed == selected { String::new() } else { format!(" on {}", record.completed) };
+                format!("solved in {} with {} hints{}", game::format_time(record.time), record.hints, late)
+            }
+            None => "not solved".to_string(),
+        };
+        let info = format!("{}  {}  {}", selected, selected.puzzle(), status);
+        text(&info, -self.offset, top - height * 15.5, self.size, primary);
+        text("Times in gray were solved after their day and do not count for the streak.", -self.offset, top - height * 16.5, self.size, secondary);
+    }
+
+    fn draw_selection(&self, draw: &Draw) {
+        if let Some(active) = self.sudoku.active_cell().filter(|_| self.sudoku.is_active()) {
             if self.higlight_relevant {
-                self.highlight_relevant(draw, self.sudoku.active_indx);
+                self.highlight_relevant(draw, active);
             }
         } else if let Some(indx) = self.selected {
             let x = (indx % 9) as f32 * self.size / 9.0 - self.size / 2.0;
             let y = (indx / 9) as f32 * self.size / 9.0 - self.size / 2.0;
-            let primary_color_with_alpha = Rgba {
-                color: self.theme.primary_color,
+            let highlight = Rgba {
+                color: self.theme.highlight_color,
                 alpha: self.theme.theme_alpha,
             };
             draw.rect()
                 .x_y(x + self.size / 18.0 - self.offset, y + self.size / 18.0)
                 .w_h(self.size / 9.0, self.size / 9.0)
                 .z(3.0)
-                .color(primary_color_with_alpha);
+                .color(highlight);
+            // A frame in full color, so the keyboard cursor stands out on every theme.
+            draw.rect()
+                .x_y(x + self.size / 18.0 - self.offset, y + self.size / 18.0)
+                .w_h(self.size / 9.0 - 2.0, self.size / 9.0 - 2.0)
+                .z(3.0)
+                .no_fill()
+                .stroke(self.theme.highlight_color)
+                .stroke_weight(2.0);
 
             if self.higlight_relevant {
                 self.highlight_relevant(draw, indx);
@@ -224,6 +938,48 @@ impl Model {
         }
     }
 
+    fn heatmap_counts(&self) -> Option<&[u32; 81]> {
+        match self.heatmap {
+            Heatmap::Off => None,
+            Heatmap::Visits => Some(&self.sudoku.stats().visits),
+            Heatmap::Writes => Some(&self.sudoku.stats().writes),
+        }
+    }
+
+    fn draw_heatmap(&self, draw: &Draw) {
+        let Some(counts) = self.heatmap_counts() else {
+            return;
+        };
+        // Logarithmic scale, the hardest cells are often visited orders of magnitude more often.
+        let max = (*counts.iter().max().unwrap() as f32).ln_1p();
+        if max == 0.0 {
+            return;
+        }
+        for (indx, count) in counts.iter().enumerate().filter(|(_, c)| **c > 0) {
+            let x = (indx % 9) as f32 * self.size / 9.0 - self.size / 2.0;
+            let y = (indx / 9) as f32 * self.size / 9.0 - self.size / 2.0;
+            draw.rect()
+                .x_y(x + self.size / 18.0 - self.offset, y + self.size / 18.0)
+                .w_h(self.size / 9.0, self.size / 9.0)
+                .z(0.5)
+                .color(self.theme.heat_color((*count as f32).ln_1p() / max));
+        }
+    }
+
+    fn draw_breakpoints(&self, draw: &Draw) {
+        for b in self.sudoku.breakpoints.iter() {
+            if let Breakpoint::CellAssigned(indx) = b {
+                let x = (indx % 9) as f32 * self.size / 9.0 - self.size / 2.0;
+                let y = (indx / 9) as f32 * self.size / 9.0 - self.size / 2.0;
+                draw.ellipse()
+                    .x_y(x + self.size / 60.0 - self.offset, y + self.size / 9.0 - self.size / 60.0)
+                    .radius(self.size / 150.0)
+                    .z(5.0)
+                    .color(color::rgb(220u8, 50, 50));
+            }
+        }
+    }
+
     fn highlight_relevant(&self, draw: &Draw, indx: usize) {
         for tile in Sudoku::squares_iter(indx) {
             let x = (tile % 9) as f32 * self.size / 9.0 - self.size / 2.0;
@@ -241,57 +997,539 @@ impl Model {
         }
     }
     
+    fn game_time(&self) -> String {
+        let time = game::format_time(self.game.elapsed());
+        match (self.game.finished, self.game.paused) {
+            (true, _) => format!("{} Solved!", time),
+            (false, true) => format!("{} (paused)", time),
+            (false, false) => time,
+        }
+    }
+
+    fn panel_items(&self) -> Vec<PanelItem> {
+        use LabelKind::*;
+        use PanelItem::*;
+        if self.browser.is_some() {
+            return vec![
+                Label(Title, "Sudoku".to_string()),
+                Label(Heading, "Puzzles:".to_string()),
+                Label(Hint, self.bindings.hint(&[Action::BrowseUp, Action::BrowseDown], "Select puzzle")),
+                Label(Hint, self.bindings.hint(&[Action::BrowsePreviousPage, Action::BrowseNextPage], "Previous / next page")),
+                Label(Hint, self.bindings.hint(&[Action::LoadPuzzle], "Load puzzle")),
+                Label(Hint, format!("[0-9] {}", self.bindings.hint(&[Action::LoadPuzzle], "Go to line"))),
+                Label(Hint, self.bindings.hint(&[Action::NextCollection], "Next collection")),
+                Label(Hint, self.bindings.hint(&[Action::FilterClues], "Filter by clues")),
+                Label(Hint, self.bindings.hint(&[Action::FilterGuesses], "Filter by guesses")),
+                Label(Hint, self.bindings.hint(&[Action::FilterSolved], "Filter by solved")),
+                Label(Hint, "Drop a .sdm file to import it".to_string()),
+                Label(Hint, self.bindings.hint(&[Action::Browse], "Close")),
+            ];
+        }
+        if self.calendar.is_some() {
+            return vec![
+                Label(Title, "Sudoku".to_string()),
+                Label(Heading, "Daily puzzle:".to_string()),
+                Label(Hint, self.bindings.hint(&[Action::PreviousDay, Action::NextDay], "Previous / next day")),
+                Label(Hint, self.bindings.hint(&[Action::PreviousWeek, Action::NextWeek], "Previous / next week")),
+                Label(Hint, self.bindings.hint(&[Action::PreviousMonth, Action::NextMonth], "Previous / next month")),
+                Label(Hint, self.bindings.hint(&[Action::Today], "Today")),
+                Label(Hint, self.bindings.hint(&[Action::PlayDaily], "Play puzzle of the day")),
+                Label(Hint, self.bindings.hint(&[Action::Daily], "Close")),
+            ];
+        }
+        let breakpoints = match self.sudoku.breakpoints.is_empty() {
+            true => "none".to_string(),
+            false => self.sudoku.breakpoints.iter().map(|b| b.to_string()).collect::<Vec<_>>().join(", "),
+        };
+        let heatmap_max = match self.heatmap_counts() {
+            Some(counts) => format!(" (max {})", counts.iter().max().unwrap()),
+            None => String::new(),
+        };
+        let stats = self.sudoku.stats();
+        let running = self.sudoku.is_running();
+        let idle = !self.sudoku.is_active() && self.race.is_none();
+        let button = |text: &str, action: Action, hint: &str| {
+            Control::new(ControlKind::Button, text, Click::Action(action)).hint(self.bindings.hint(&[action], hint))
+        };
+        let toggle = |text: &str, action: Action, on: bool| {
+            Control::new(ControlKind::Toggle(on), text, Click::Action(action)).hint(self.bindings.hint(&[action], text))
+        };
+        let difficulty = |difficulty: Difficulty| {
+            Control::new(ControlKind::Choice(difficulty == self.difficulty), difficulty.to_string(), Click::Difficulty(difficulty))
+                .hint(self.bindings.hint(&[Action::Easier, Action::Harder], "Easier / harder"))
+        };
+        let start = match (&self.race, self.sudoku.state) {
+            (Some(race), _) if race.paused => "Resume",
+            (Some(_), _) | (None, SolverState::Running) => "Pause",
+            (None, SolverState::Idle) => "Start",
+            (None, SolverState::Paused) => "Resume",
+            (None, SolverState::NoSolution | SolverState::SolutionFound | SolverState::GaveUp) => "Reset",
+        };
+        let speed = ControlKind::Slider(controls::speed_position(self.sudoku.steps_per_frame()));
+
+        let mut items = vec![
+            Label(Title, "Sudoku".to_string()),
+
+            Label(Heading, "Solver:".to_string()),
+            Label(Value, format!("Algorithm: {}", self.sudoku.solver.to_string())),
+            Label(Value, format!("State: {}", self.sudoku.state.to_string())),
+            Label(Value, format!("Steps per frame: {:.3}", self.sudoku.real_steps_per_frame)),
+            Label(Value, format!("Steps per second: {:.0}", self.fps * self.sudoku.real_steps_per_frame)),
+            Label(Value, format!("Current Steps: {}", stats.steps)),
+            Label(Value, format!("Depth: {}  Backtracks: {}", stats.depth, stats.backtracks)),
+        ];
+        items.extend(self.sudoku.solver_status().into_iter().map(|line| Label(Value, line)));
+        if let Some(race) = &self.race {
+            let finished = race.racers.iter().filter(|racer| racer.is_finished()).count();
+            items.push(Label(Value, match race.is_finished() {
+                true => "Race: Finished".to_string(),
+                false => format!("Race: {} of {} finished", finished, race.racers.len()),
+            }));
+        }
+        items.extend([
+            Label(Value, match self.sudoku.solver.has_breakpoints() {
+                true => format!("Breakpoints: {}", breakpoints),
+                false => "Breakpoints: not used by this algorithm".to_string(),
+            }),
+            Controls(vec![
+                button(start, Action::StartPause, "Start / Pause / Resume"),
+                button("Solve", Action::SolveInstantly, "Solve instantly").enabled(!running),
+                button("Clear", Action::ClearResult, "Clear Result").enabled(!running),
+            ]),
+            Controls(vec![
+                button("< Step", Action::StepBack, "Step back").enabled(self.sudoku.solver.has_history()),
+                button("Step >", Action::StepForward, "Step forward"),
+                button("Algorithm", Action::ChangeAlgorithm, "Change algorithm").enabled(!self.sudoku.is_active()),
+            ]),
+            Controls(vec![
+                Control::new(speed, "Speed", Click::Speed).hint(self.bindings.hint(&[Action::Slower, Action::Faster], "Slower / faster")),
+            ]),
+            Label(Hint, self.bindings.hint(&[Action::Race], "Start / end race of all algorithms")),
+            Label(Hint, self.bindings.hint(&[Action::CellBreakpoint], "Break on selected Tile")),
+            Label(Hint, self.bindings.hint(&[Action::DepthBreakpoint, Action::BacktrackBreakpoint], "Break on depth / backtrack")),
+
+            Label(Heading, "Game:".to_string()),
+            Label(Value, format!("Time: {}", self.game_time())),
+            Label(Hint, self.bindings.hint(&[Action::CursorUp, Action::CursorDown, Action::CursorLeft, Action::CursorRight], "Move cursor")),
+            Label(Hint, self.bindings.hint(&[Action::NextEmpty], "Next empty Tile, [Shift] previous")),
+            Label(Hint, "[1-9] [0] Enter / erase digit".to_string()),
+            Controls(vec![toggle("Digit first", Action::DigitFirst, self.digit_first)]),
+            Label(Value, format!("Hints: {}", self.game.hints)),
+            Label(Value, match self.game.daily {
+                Some(day) => format!("Daily: {}, streak {}", day, self.daily.streak(Day::today())),
+                None => format!("Daily streak: {}", self.daily.streak(Day::today())),
+            }),
+            Label(Hint, self.bindings.hint(&[Action::PauseTimer], "Pause / resume timer")),
+            Label(Hint, self.bindings.hint(&[Action::Hint], "Hint")),
+            Label(Hint, self.bindings.hint(&[Action::Statistics], "Statistics")),
+            Label(Hint, self.bindings.hint(&[Action::Daily], "Daily puzzle")),
+
+            Label(Heading, "Difficulty:".to_string()),
+            Controls(vec![difficulty(Difficulty::Easy), difficulty(Difficulty::Medium)]),
+            Controls(vec![difficulty(Difficulty::Hard), difficulty(Difficulty::VeryHard)]),
+            Label(Value, format!("Puzzle: {}", self.sudoku.id.map_or("custom".to_string(), |id| id.to_string()))),
+            Label(Value, format!("Seed: {}", self.sudoku.seed)),
+            Controls(vec![
+                button("New", Action::NewPuzzle, "Load new Sudoku"),
+                button("Browse", Action::Browse, "Browse puzzles").enabled(idle),
+                button("Clear all", Action::ClearSudoku, "Clear Sudoku").enabled(!running),
+            ]),
+
+            Label(Heading, "Settings:".to_string()),
+            Controls(vec![
+                Control::new(ControlKind::Button, "<", Click::Theme(-1)).hint(self.bindings.hint(&[Action::Theme], "Next color theme")),
+                Control::new(ControlKind::Button, &self.theme.name, Click::Theme(1))
+                    .hint(self.bindings.hint(&[Action::Theme], "Next color theme"))
+                    .weight(4.0),
+                Control::new(ControlKind::Button, ">", Click::Theme(1)).hint(self.bindings.hint(&[Action::Theme], "Next color theme")),
+            ]),
+            Controls(vec![toggle("Available numbers", Action::ShowAvailable, self.show_available)]),
+            Controls(vec![toggle("Highlight relevant tiles", Action::HighlightRelevant, self.higlight_relevant)]),
+            Controls(vec![toggle("Shape cues", Action::ShapeCues, self.shape_cues)]),
+            Label(Value, format!("Text size: {:.0}%", self.font_scale * 100.0)),
+            Label(Hint, self.bindings.hint(&[Action::FontSmaller, Action::FontLarger], "Smaller / larger Text")),
+            Label(Value, format!("Heatmap: {}{}", self.heatmap.to_string(), heatmap_max)),
+            Label(Hint, self.bindings.hint(&[Action::Heatmap], "Cycle Heatmap")),
+            Label(Value, format!("Recording: {}", self.recorder.status)),
+            Label(Hint, self.bindings.hint(&[Action::Record], &format!("Start / stop recording ({})", self.recorder.format.to_string()))),
+            Label(Hint, self.bindings.hint(&[Action::RecordingFormat], "Change recording format")),
+            Label(Value, format!("Export: {}", self.export_status)),
+            Label(Hint, self.bindings.hint(&[Action::Export], "Export SVG / PDF / CNF / SDM, [Shift] givens only")),
+            Label(Hint, self.bindings.hint(&[Action::Fullscreen], "Toggle Fullscreen")),
+            Label(Hint, self.bindings.hint(&[Action::Quit], "Close application")),
+        ]);
+        items
+    }
+
+    /// Lays out the side panel from the top. Shrinks the text if the panel would run into
+    /// the timeline at the bottom, larger text only grows as far as there is room. Returns
+    /// the lines with their area and the scale of the text.
+    fn panel_layout(&self) -> (Vec<(Rect, PanelItem)>, f32) {
+        let items = self.panel_items();
+        let height: f32 = items.iter().map(|item| item.height(self.gui_width)).sum();
+        let available = self.size - self.timeline_rect().h() * 3.0;
+        let scale = (available / height).min(self.font_scale);
+
+        let x = self.size / 2.0 - self.offset + self.gui_width / 2.0 + 15.0;
+        let mut top = self.size / 2.0;
+        let layout = items
+            .into_iter()
+            .map(|mut item| {
+                let height = item.height(self.gui_width) * scale;
+                let rect = Rect::from_x_y_w_h(x, top - height / 2.0, self.gui_width, height);
+                if let PanelItem::Controls(controls) = &mut item {
+                    controls::place_row(controls, rect);
+                }
+                top -= height;
+                (rect, item)
+            })
+            .collect();
+        (layout, scale)
+    }
+
+    /// Action of the control of the side panel or the digit pad under the point.
+    pub fn control_at(&self, point: Point2) -> Option<Click> {
+        if let Some(control) = self.pad_controls().and_then(|pad| controls::control_at(&pad, point).map(|control| control.click)) {
+            return Some(control);
+        }
+        let (layout, _) = self.panel_layout();
+        layout.iter().find_map(|(_, item)| match item {
+            PanelItem::Controls(controls) => controls::control_at(controls, point).map(|control| control.click),
+            PanelItem::Label(..) => None,
+        })
+    }
+
+    /// Buttons of the digit pad under the board, the digits and erase. `None` while a view
+    /// replaces the board.
+    fn pad_controls(&self) -> Option<Vec<Control>> {
+        if self.race.is_some() || self.browser.is_some() || self.calendar.is_some() || self.show_stats || self.game.paused {
+            return None;
+        }
+        let enabled = !self.sudoku.is_active();
+        let picked = |digit| ControlKind::Choice(self.digit_first && self.pad_digit == Some(digit));
+        let mut pad: Vec<Control> = (1..=9)
+            .map(|digit| Control::new(picked(digit), &digit.to_string(), Click::Digit(digit)).enabled(enabled))
+            .collect();
+        pad.push(Control::new(picked(0), "Erase", Click::Digit(0)).enabled(enabled).weight(1.5));
+        let height = self.size * PAD_HEIGHT;
+        let rect = Rect::from_x_y_w_h(-self.offset, -self.size / 2.0, self.size, height);
+        controls::place_row(&mut pad, rect);
+        Some(pad)
+    }
+
+    /// Draws the digit pad with the number of tiles still missing each digit.
+    fn draw_pad(&self, draw: &Draw) {
+        let Some(pad) = self.pad_controls() else {
+            return;
+        };
+        let height = self.size * PAD_HEIGHT;
+        let remaining = self.sudoku.remaining();
+        for control in &pad {
+            let Click::Digit(digit) = control.click else {
+                continue;
+            };
+            if digit == 0 {
+                self.draw_control(draw, control, (height * 0.3) as u32);
+                continue;
+            }
+            self.draw_control(draw, control, (height * 0.5) as u32);
+            let rect = control.rect;
+            self.text(draw, &remaining[digit as usize - 1].to_string())
+                .x_y(rect.right() - rect.w() * 0.15, rect.bottom() + rect.h() * 0.2)
+                .w(rect.w() * 0.3)
+                .font_size((height * 0.22) as u32)
+                .color(self.theme.secondary_color);
+        }
+    }
+
+    /// Sets the speed of the solvers while the speed slider is dragged. Returns false if
+    /// the drag did not start on the slider.
+    pub fn drag_speed_slider(&mut self, pressed: Point2, x: f32) -> bool {
+        let (layout, _) = self.panel_layout();
+        let slider = layout.iter().find_map(|(_, item)| match item {
+            PanelItem::Controls(controls) => controls.iter().find(|control| control.click == Click::Speed && control.rect.contains(pressed)),
+            PanelItem::Label(..) => None,
+        });
+        let Some(slider) = slider else {
+            return false;
+        };
+        self.sudoku.set_steps_per_frame(controls::speed_at(slider.slider_position(x)));
+        true
+    }
+
     fn draw_gui(&self, draw: &Draw) {
-        let title_size = (self.gui_width / 6.0) as u32;
-        let sub_title_size = (self.gui_width / 8.0) as u32;
-        let text_size = (self.gui_width / 15.0) as u32;
-
-        let mut y = self.size / 2.0;
-        self.add_label(draw, "Sudoku", &mut y, title_size, self.theme.primary_color);
-
-        self.add_label(draw, "Solver:", &mut y, sub_title_size, self.theme.primary_color);
-        self.add_label(draw, &format!("State: {}", self.sudoku.state.to_string()), &mut y, text_size, self.theme.primary_color);
-        self.add_label(draw, &format!("Steps per frame: {:.3}", self.sudoku.real_steps_per_frame), &mut y, text_size, self.theme.primary_color);
-        self.add_label(draw, &format!("Steps per second: {:.0}", self.fps * self.sudoku.real_steps_per_frame as f32), &mut y, text_size, self.theme.primary_color);
-        self.add_label(draw, &format!("Current Steps: {}", self.sudoku.step_count), &mut y, text_size, self.theme.primary_color);
-        self.add_label(draw, &"[Space] Toggle solver", &mut y, text_size, self.theme.secondary_color);
-        self.add_label(draw, &"[E] Clear Result", &mut y, text_size, self.theme.secondary_color);
-        self.add_label(draw, &"[Up] Step faster", &mut y, text_size, self.theme.secondary_color);
-        self.add_label(draw, &"[Down] Step slower", &mut y, text_size, self.theme.secondary_color);
-        
-        self.add_label(draw, "Difficulty:", &mut y, sub_title_size, self.theme.primary_color);
-        self.add_label(draw, &format!("Selected: {}", self.sudoku.difficulty.to_string()), &mut y, text_size, self.theme.primary_color);
-        self.add_label(draw, &"[Left] Easier Difficulty", &mut y, text_size, self.theme.secondary_color);
-        self.add_label(draw, &"[Right] Harder Difficulty", &mut y, text_size, self.theme.secondary_color);
-        self.add_label(draw, &"[R] Load new Sudoku", &mut y, text_size, self.theme.secondary_color);
-        self.add_label(draw, &"[W] Clear Sudoku", &mut y, text_size, self.theme.secondary_color);
-
-        self.add_label(draw, "Settings:", &mut y, sub_title_size, self.theme.primary_color);
-        self.add_label(draw, &format!("Color Theme: {}", self.theme.theme_type.to_string()), &mut y, text_size, self.theme.primary_color);
-        self.add_label(draw, &"[T] Change Color Theme", &mut y, text_size, self.theme.secondary_color);
-        self.add_label(draw, &"[Z] Toggle Available Numbers", &mut y, text_size, self.theme.secondary_color);
-        self.add_label(draw, &"[U] Highlight relevant Tiles", &mut y, text_size, self.theme.secondary_color);
-        self.add_label(draw, &"[F11] Toggle Fullscreen", &mut y, text_size, self.theme.secondary_color);
-        self.add_label(draw, &"[Escape] Close application", &mut y, text_size, self.theme.secondary_color);
-
-    }
-
-    fn add_label(
-        &self,
-        draw: &Draw,
-        text: &str,
-        y: &mut f32,
-        font_size: u32,
-        color: rgb::Rgb<color::encoding::Srgb, u8>,
-    ) {
+        let (layout, scale) = self.panel_layout();
+        let font_size = (LabelKind::Value.font_size(self.gui_width) * scale) as u32;
+        let mut hovered = None;
+        for (rect, item) in layout.iter() {
+            match item {
+                PanelItem::Label(kind, text) => {
+                    let color = match kind {
+                        LabelKind::Hint => self.theme.secondary_color,
+                        _ => self.theme.primary_color,
+                    };
+                    self.text(draw, text)
+                        .xy(rect.xy())
+                        .w(rect.w())
+                        .left_justify()
+                        .font_size((kind.font_size(self.gui_width) * scale) as u32)
+                        .color(color);
+                }
+                PanelItem::Controls(controls) => {
+                    for control in controls {
+                        self.draw_control(draw, control, font_size);
+                    }
+                    hovered = hovered.or(controls::control_at(controls, self.mouse));
+                }
+            }
+        }
+        if let Some(control) = hovered {
+            self.draw_tooltip(draw, &control.hint, font_size);
+        }
+    }
+
+    /// Draws a control, lighter while the mouse is over it and darker while it is pressed.
+    fn draw_control(&self, draw: &Draw, control: &Control, font_size: u32) {
+        let rect = control.rect;
+        let hovered = control.enabled && rect.contains(self.mouse);
+        let alpha = match (hovered, self.mouse_down) {
+            (true, true) => self.theme.theme_alpha.saturating_mul(6),
+            (true, false) => self.theme.theme_alpha.saturating_mul(4),
+            (false, _) => self.theme.theme_alpha.saturating_mul(2),
+        };
+        let text_color = match control.enabled {
+            true => self.theme.primary_color,
+            false => self.theme.secondary_color,
+        };
+        match control.kind {
+            ControlKind::Button | ControlKind::Choice(_) => {
+                let (fill, border) = match control.kind {
+                    ControlKind::Choice(true) => (self.theme.highlight_color, self.theme.primary_color),
+                    _ => (self.theme.secondary_color, self.theme.secondary_color),
+                };
+                draw.rect()
+                    .xy(rect.xy())
+                    .wh(rect.wh())
+                    .color(Rgba { color: fill, alpha })
+                    .stroke(border)
+                    .stroke_weight(1.0);
+                self.text(draw, &control.text)
+                    .xy(rect.xy())
+                    .w(rect.w())
+                    .font_size(font_size)
+                    .color(text_color);
+            }
+            ControlKind::Toggle(on) => {
+                if hovered {
+                    draw.rect()
+                        .xy(rect.xy())
+                        .wh(rect.wh())
+                        .color(Rgba { color: self.theme.secondary_color, alpha });
+                }
+                let side = rect.h() * 0.6;
+                let center = pt2(rect.left() + rect.h() / 2.0, rect.y());
+                draw.rect()
+                    .xy(center)
+                    .w_h(side, side)
+                    .no_fill()
+                    .stroke(text_color)
+                    .stroke_weight(1.5);
+                if on {
+                    draw.rect()
+                        .xy(center)
+                        .w_h(side * 0.55, side * 0.55)
+                        .color(self.theme.highlight_color);
+                }
+                let width = rect.w() - rect.h();
+                self.text(draw, &control.text)
+                    .x_y(rect.right() - width / 2.0, rect.y())
+                    .w(width)
+                    .left_justify()
+                    .font_size(font_size)
+                    .color(text_color);
+            }
+            ControlKind::Slider(position) => {
+                let track = control.track();
+                let width = rect.w() - track.w();
+                self.text(draw, &control.text)
+                    .x_y(rect.left() + width / 2.0, rect.y())
+                    .w(width)
+                    .left_justify()
+                    .font_size(font_size)
+                    .color(text_color);
+                let knob_x = track.left() + position * track.w();
+                draw.line()
+                    .start(pt2(track.left(), track.y()))
+                    .end(pt2(track.right(), track.y()))
+                    .stroke_weight(2.0)
+                    .color(self.theme.secondary_color);
+                draw.line()
+                    .start(pt2(track.left(), track.y()))
+                    .end(pt2(knob_x, track.y()))
+                    .stroke_weight(2.0)
+                    .color(self.theme.primary_color);
+                draw.ellipse()
+                    .x_y(knob_x, track.y())
+                    .radius(rect.h() / if hovered { 4.0 } else { 5.0 })
+                    .color(self.theme.primary_color);
+            }
+        }
+    }
+
+    /// Shows the keys of the control under the mouse above it.
+    fn draw_tooltip(&self, draw: &Draw, hint: &str, font_size: u32) {
+        if hint.is_empty() {
+            return;
+        }
+        let width = self.gui_width * 1.2;
+        let height = font_size as f32 * 1.8;
+        let right = self.window_width as f32 / 2.0 - 5.0;
+        let x = (self.mouse.x).min(right - width / 2.0);
+        let rect = Rect::from_x_y_w_h(x, self.mouse.y + height, width, height);
+        draw.rect()
+            .xy(rect.xy())
+            .wh(rect.wh())
+            .color(self.theme.tile_color)
+            .stroke(self.theme.secondary_color)
+            .stroke_weight(1.0);
+        self.text(draw, hint)
+            .xy(rect.xy())
+            .w(width)
+            .font_size(font_size)
+            .color(self.theme.primary_color);
+    }
+
+    /// True if the solver timeline is shown, only together with the board.
+    pub fn shows_timeline(&self) -> bool {
+        self.sudoku.solver.has_history()
+            && self.race.is_none()
+            && self.browser.is_none()
+            && self.calendar.is_none()
+            && !self.show_stats
+            && !self.game.paused
+    }
+
+    /// Area of the solver timeline slider at the bottom of the side panel.
+    pub fn timeline_rect(&self) -> Rect {
         let x = self.size / 2.0 - self.offset + self.gui_width / 2.0 + 15.0;
-        *y -= font_size as f32 * 0.75;
-        draw.text(text)
-            .x_y(x, *y)
-            .w(self.gui_width)
+        Rect::from_x_y_w_h(x, -self.size / 2.0 + 8.0, self.gui_width, 16.0)
+    }
+
+    /// Trace position under the given x coordinate of the timeline slider.
+    pub fn timeline_position(&self, x: f32) -> usize {
+        let rect = self.timeline_rect();
+        let fraction = ((x - rect.left()) / rect.w()).clamp(0.0, 1.0);
+        self.sudoku.history_start() + (fraction * self.sudoku.history().len() as f32).round() as usize
+    }
+
+    fn draw_timeline(&self, draw: &Draw) {
+        let rect = self.timeline_rect();
+        let start = self.sudoku.history_start();
+        let len = self.sudoku.history().len();
+        let fraction = match len {
+            0 => 0.0,
+            len => (self.sudoku.trace_position() - start) as f32 / len as f32,
+        };
+        let knob_x = rect.left() + fraction * rect.w();
+        draw.line()
+            .start(pt2(rect.left(), rect.y()))
+            .end(pt2(rect.right(), rect.y()))
+            .stroke_weight(2.0)
+            .color(self.theme.secondary_color);
+        draw.line()
+            .start(pt2(rect.left(), rect.y()))
+            .end(pt2(knob_x, rect.y()))
+            .stroke_weight(2.0)
+            .color(self.theme.primary_color);
+        draw.ellipse()
+            .x_y(knob_x, rect.y())
+            .radius(rect.h() / 3.0)
+            .color(self.theme.primary_color);
+        self.text(draw, &format!("History: {} / {}", self.sudoku.trace_position(), start + len))
+            .x_y(rect.x(), rect.top() + rect.h() / 2.0)
+            .w(rect.w())
             .left_justify()
-            .font_size(font_size)
-            .color(color);
-        *y -= font_size as f32 * 0.75;
+            .font_size(self.font_size(self.gui_width / 15.0))
+            .color(self.theme.secondary_color);
+    }
+
+    /// Font size of text of the given default size, scaled by the font scale.
+    fn font_size(&self, size: f32) -> u32 {
+        (size * self.font_scale) as u32
+    }
+
+    /// Text in the font of the theme.
+    fn text<'a>(&self, draw: &'a Draw, text: &str) -> DrawingText<'a> {
+        match &self.theme.font {
+            Some((_, font)) => draw.text(text).font(font.clone()),
+            None => draw.text(text),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loaded_puzzles_keep_the_selected_difficulty() {
+        let mut model = Model { difficulty: Difficulty::Easy, ..Model::default() };
+        let sunday = Day::from_date(2024, 3, 31);
+        model.play_daily(sunday);
+        assert_eq!(model.sudoku.difficulty, Difficulty::VeryHard);
+        assert_eq!(model.current_settings().difficulty, Difficulty::Easy);
+
+        model.load_random();
+        assert_eq!(model.sudoku.id.unwrap().difficulty, Difficulty::Easy);
+        model.set_difficulty(Difficulty::Hard);
+        assert_eq!(model.current_settings().difficulty, Difficulty::Hard);
+        assert_eq!(model.sudoku.id.unwrap().difficulty, Difficulty::Hard);
+    }
+
+    #[test]
+    fn opening_the_daily_again_continues_it() {
+        let mut model = Model::default();
+        let day = Day::from_date(2024, 3, 25);
+        model.play_daily(day);
+        let empty = model.sudoku.tiles.iter().position(|tile| *tile == Tile::Empty).unwrap();
+        let digit = model.sudoku.candidates(empty).trailing_zeros() as u8;
+        model.selected = Some(empty);
+        model.try_write_tile(Tile::PlayerVariable(digit));
+        let tiles = model.sudoku.tiles;
+        assert!(model.game.is_started());
+
+        model.toggle_calendar();
+        model.play_daily(day);
+        assert!(model.calendar.is_none());
+        assert_eq!(model.sudoku.tiles, tiles);
+        assert_eq!(model.game.moves, 1);
+        assert_eq!(model.game.daily, Some(day));
+    }
+
+    #[test]
+    fn finished_games_cannot_be_changed() {
+        let mut model = Model::default();
+        model.sudoku.load_line(Difficulty::Easy.puzzles().lines().next().unwrap());
+        model.update_game();
+        let solution = Bitboard::new(&model.sudoku.tiles).and_then(|mut board| board.solve()).unwrap();
+        let last = model.sudoku.tiles.iter().rposition(|tile| *tile == Tile::Empty).unwrap();
+        for (tile, digit) in model.sudoku.tiles.iter_mut().zip(solution).take(last) {
+            if *tile == Tile::Empty {
+                *tile = Tile::PlayerVariable(digit);
+            }
+        }
+        model.selected = Some(last);
+        model.try_write_tile(Tile::PlayerVariable(solution[last]));
+        assert!(model.game.finished);
+
+        let (tiles, moves) = (model.sudoku.tiles, model.game.moves);
+        model.try_write_tile(Tile::Empty);
+        model.hint();
+        assert_eq!(model.sudoku.tiles, tiles);
+        assert_eq!(model.game.moves, moves);
+
+        model.sudoku.tiles = [Tile::Empty; 81];
+        model.update_game();
+        assert!(!model.game.finished);
     }
 }
@@ -0,0 +1,152 @@
+use std::time::{Duration, Instant};
+
+use crate::solver::SolverKind;
+use crate::sudoku::{SolverState, Sudoku};
+
+/// A solver taking part in a race.
+pub struct Racer {
+    pub sudoku: Sudoku,
+    /// Time spent in the solver, the drawing and pauses are not counted.
+    pub elapsed: Duration,
+    /// Finishing place, starting at 1. Solvers that give up or find no solution get none.
+    pub place: Option<usize>,
+}
+
+impl Racer {
+    pub fn is_finished(&self) -> bool {
+        !self.sudoku.is_running()
+    }
+
+    pub fn steps(&self) -> u64 {
+        self.sudoku.stats().steps
+    }
+
+    /// Takes the steps up to `budget`. Solvers with expensive steps take fewer per call and
+    /// catch up in the next frames.
+    fn run_to(&mut self, budget: u64, time_limit: Duration) {
+        let start = Instant::now();
+        let steps = budget.saturating_sub(self.steps()).min(u32::MAX as u64) as u32;
+        self.sudoku.advance(steps);
+        self.elapsed += start.elapsed();
+        if self.sudoku.is_running() && self.elapsed >= time_limit {
+            self.sudoku.state = SolverState::GaveUp;
+        }
+    }
+}
+
+/// Several solvers working on copies of the same puzzle. Every frame each of them gets the
+/// same number of steps, so their step counters stay comparable. The solvers are placed by
+/// the steps they needed, the time spent in the solver is shown alongside.
+pub struct Race {
+    pub racers: Vec<Racer>,
+    pub paused: bool,
+    /// Steps every racer may have taken so far.
+    budget: u64,
+    /// Solver time after which a racer gives up.
+    pub time_limit: Duration,
+}
+
+impl Race {
+    const TIME_LIMIT: Duration = Duration::from_secs(60);
+
+    pub fn new(sudoku: &Sudoku, kinds: &[SolverKind]) -> Self {
+        let racers = kinds
+            .iter()
+            .map(|kind| {
+                let mut sudoku = sudoku.clone();
+                sudoku.clear_variables();
+                sudoku.reset_solver();
+                sudoku.breakpoints.clear();
+                sudoku.trace_limit = 0;
+                sudoku.solver = *kind;
+                sudoku.state = SolverState::Running;
+                Racer {
+                    sudoku,
+                    elapsed: Duration::ZERO,
+                    place: None,
+                }
+            })
+            .collect();
+        Race {
+            racers,
+            paused: false,
+            budget: 0,
+            time_limit: Race::TIME_LIMIT,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.racers.iter().all(Racer::is_finished)
+    }
+
+    /// Gives every solver that has not finished `steps` more steps. A solver that found a
+    /// solution is placed once no running solver can still get by with fewer steps.
+    pub fn step(&mut self, steps: u32) {
+        if self.paused {
+            return;
+        }
+        self.budget += steps as u64;
+        for racer in self.racers.iter_mut().filter(|racer| !racer.is_finished()) {
+            racer.run_to(self.budget, self.time_limit);
+        }
+        let running = self.racers.iter().filter(|racer| !racer.is_finished()).map(Racer::steps).min().unwrap_or(u64::MAX);
+        let mut solved: Vec<usize> = (0..self.racers.len())
+            .filter(|i| {
+                let racer = &self.racers[*i];
+                racer.place.is_none() && matches!(racer.sudoku.state, SolverState::SolutionFound) && racer.steps() <= running
+            })
+            .collect();
+        solved.sort_by_key(|i| self.racers[*i].steps());
+        let mut placed = self.racers.iter().filter(|racer| racer.place.is_some()).count();
+        for i in solved {
+            placed += 1;
+            self.racers[i].place = Some(placed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PUZZLE: &str = "530070000600195000098000060800060003400803001700020006060000280000419005000080079";
+
+    #[test]
+    fn places_by_steps() {
+        let sudoku = Sudoku::from_line(PUZZLE).unwrap();
+        let mut race = Race::new(&sudoku, &[SolverKind::Backtracking, SolverKind::DancingLinks]);
+        while !race.is_finished() {
+            race.step(1000);
+        }
+        let mut placed: Vec<&Racer> = race.racers.iter().collect();
+        placed.sort_by_key(|racer| racer.place);
+        assert_eq!(placed.iter().map(|racer| racer.place).collect::<Vec<_>>(), [Some(1), Some(2)]);
+        assert!(placed[0].steps() <= placed[1].steps());
+    }
+
+    #[test]
+    fn racers_get_the_same_steps() {
+        let sudoku = Sudoku::from_line(PUZZLE).unwrap();
+        let mut race = Race::new(&sudoku, &[SolverKind::Backtracking, SolverKind::DancingLinks, SolverKind::Annealing]);
+        for _ in 0..3 {
+            race.step(5);
+        }
+        for racer in race.racers.iter().filter(|racer| !racer.is_finished()) {
+            assert_eq!(racer.steps(), 15, "{}", racer.sudoku.solver.to_string());
+        }
+    }
+
+    #[test]
+    fn stalled_racers_give_up() {
+        // The first tile can only hold a one, which is already in its column.
+        let line = format!("023456789{}100000000{}", "0".repeat(27), "0".repeat(36));
+        let sudoku = Sudoku::from_line(&line).unwrap();
+        let mut race = Race::new(&sudoku, &[SolverKind::Annealing]);
+        race.time_limit = Duration::from_millis(20);
+        while !race.is_finished() {
+            race.step(1000);
+        }
+        assert!(matches!(race.racers[0].sudoku.state, SolverState::GaveUp));
+        assert_eq!(race.racers[0].place, None);
+    }
+}
@@ -0,0 +1,192 @@
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use nannou::image::{self, codecs::gif::{GifEncoder, Repeat}, Delay, Frame, RgbaImage};
+
+use crate::board::BoardOptions;
+use crate::render::Renderer;
+use crate::storage;
+use crate::sudoku::{SolverState, Sudoku};
+use crate::theme::Theme;
+
+const RECORDINGS_DIRECTORY: &str = "recordings";
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum RecordFormat {
+    Png,
+    Gif,
+}
+
+impl RecordFormat {
+    pub fn next(self) -> Self {
+        match self {
+            RecordFormat::Png => RecordFormat::Gif,
+            RecordFormat::Gif => RecordFormat::Png,
+        }
+    }
+
+    pub fn to_string(self) -> &'static str {
+        match self {
+            RecordFormat::Png => "PNG sequence",
+            RecordFormat::Gif => "GIF",
+        }
+    }
+}
+
+/// Captures the frames rendered to the window while recording.
+pub struct Recorder {
+    pub format: RecordFormat,
+    pub status: String,
+    directory: Option<PathBuf>,
+    frames: u32,
+}
+
+impl Default for Recorder {
+    fn default() -> Self {
+        Recorder {
+            format: RecordFormat::Png,
+            status: "Off".to_string(),
+            directory: None,
+            frames: 0,
+        }
+    }
+}
+
+impl Recorder {
+    pub fn is_recording(&self) -> bool {
+        self.directory.is_some()
+    }
+
+    /// Starts a new recording in a timestamped directory below `recordings`.
+    pub fn start(&mut self) {
+        self.directory = Some(storage::timestamped_path(Path::new(RECORDINGS_DIRECTORY)));
+        self.frames = 0;
+    }
+
+    /// Path the next captured frame is written to, if recording.
+    pub fn next_frame_path(&mut self) -> Option<PathBuf> {
+        let directory = self.directory.as_ref()?;
+        self.frames += 1;
+        self.status = format!("Recording {} frames", self.frames);
+        Some(frame_path(directory, self.frames))
+    }
+
+    /// Stops recording. All captured frames must have been written to disk already.
+    /// For the GIF format the frames are combined and the PNG files removed, they are kept
+    /// if a frame can not be read or the GIF not be written.
+    pub fn stop(&mut self, fps: f32) {
+        let Some(directory) = self.directory.take() else {
+            return;
+        };
+        let result = match self.format {
+            RecordFormat::Png => Ok(directory),
+            RecordFormat::Gif => {
+                let output = directory.with_extension("gif");
+                let frames = (1..=self.frames).map(|i| {
+                    let path = frame_path(&directory, i);
+                    image::open(&path).map(|frame| frame.to_rgba8()).map_err(|e| format!("{}: {}", path.display(), e))
+                });
+                match write_gif(&output, frames, fps) {
+                    Ok(()) => std::fs::remove_dir_all(&directory).map_err(|e| e.to_string()).map(|_| output),
+                    Err(error) => {
+                        _ = std::fs::remove_file(&output);
+                        Err(format!("{}, the frames are kept in {}", error, directory.display()))
+                    }
+                }
+            }
+        };
+        self.status = match result {
+            Ok(path) => format!("Saved {}", path.display()),
+            Err(error) => format!("Failed: {}", error),
+        };
+    }
+}
+
+fn frame_path(directory: &Path, frame: u32) -> PathBuf {
+    directory.join(format!("frame_{:06}.png", frame))
+}
+
+/// Writes the frames to an endlessly looping animated GIF. Stops at the first frame that
+/// could not be made.
+pub fn write_gif(path: &Path, frames: impl Iterator<Item = Result<RgbaImage, String>>, fps: f32) -> Result<(), String> {
+    let file = File::create(path).map_err(|e| e.to_string())?;
+    let mut encoder = GifEncoder::new_with_speed(file, 10);
+    encoder.set_repeat(Repeat::Infinite).map_err(|e| e.to_string())?;
+    let delay = Delay::from_numer_denom_ms(1000, fps.max(1.0).round() as u32);
+    for frame in frames {
+        encoder
+            .encode_frame(Frame::from_parts(frame?, 0, 0, delay))
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Settings for rendering a solver run without a window.
+pub struct HeadlessRecording {
+    pub size: u32,
+    pub fps: f32,
+    /// Solver steps between two frames, chosen automatically if `None`.
+    pub every: Option<usize>,
+    pub show_available: bool,
+    pub shape_cues: bool,
+}
+
+/// Runs the solver and renders a frame every few steps while it works. Writes an animated
+/// GIF if `output` ends in `.gif`, otherwise a PNG sequence into the directory. Returns the
+/// number of frames written.
+pub fn record_headless(mut sudoku: Sudoku, theme: &Theme, output: &Path, settings: &HeadlessRecording) -> Result<usize, String> {
+    sudoku.clear_variables();
+    sudoku.reset_solver();
+    sudoku.breakpoints.clear();
+    sudoku.trace_limit = 0;
+    sudoku.state = SolverState::Running;
+    let every = match settings.every {
+        Some(every) => every,
+        // The solvers make the same steps for the same seed, so a first run without
+        // drawing tells how long the recorded one takes.
+        None => {
+            let mut counting = sudoku.clone();
+            advance_to(&mut counting, u64::MAX);
+            counting.stats().steps as usize / 300
+        }
+    }
+    .max(1) as u64;
+
+    let options = BoardOptions {
+        show_available: settings.show_available,
+        show_active: true,
+        shape_cues: settings.shape_cues,
+        font_scale: 1.0,
+    };
+    let mut renderer = Renderer::new(settings.size, settings.size);
+    let mut count = 0;
+    let frames = std::iter::from_fn(|| {
+        if count > 0 {
+            let steps = sudoku.stats().steps;
+            if !sudoku.is_running() {
+                return None;
+            }
+            advance_to(&mut sudoku, steps + every);
+        }
+        count += 1;
+        Some(renderer.render(&sudoku, theme, options).clone())
+    });
+
+    let is_gif = output.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("gif"));
+    if is_gif {
+        write_gif(output, frames.map(Ok), settings.fps)?;
+    } else {
+        std::fs::create_dir_all(output).map_err(|e| e.to_string())?;
+        for (i, frame) in frames.enumerate() {
+            frame.save(frame_path(output, i as u32 + 1)).map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(count)
+}
+
+/// Runs the solver until it took `steps` steps or finished.
+fn advance_to(sudoku: &mut Sudoku, steps: u64) {
+    while sudoku.is_running() && sudoku.stats().steps < steps {
+        sudoku.advance((steps - sudoku.stats().steps).min(u32::MAX as u64) as u32);
+    }
+}
@@ -0,0 +1,151 @@
+use std::collections::HashSet;
+use std::time::Duration;
+
+use crate::storage;
+use crate::sudoku::Difficulty;
+
+const SOLVED_FILE: &str = "solved.sdm";
+const GAMES_FILE: &str = "games.csv";
+
+/// Statistics category of puzzles that are not from a bundled collection.
+pub const CUSTOM: &str = "Custom";
+
+/// Statistics categories, the difficulties of the bundled collections and other puzzles.
+pub fn categories() -> impl Iterator<Item = &'static str> {
+    Difficulty::ALL.into_iter().map(Difficulty::to_string).chain([CUSTOM])
+}
+
+/// A game that was completed, or left for another puzzle after the player entered a digit.
+pub struct GameRecord {
+    pub category: String,
+    pub solved: bool,
+    pub time: Duration,
+    pub hints: u32,
+    /// Givens of the puzzle.
+    pub puzzle: String,
+}
+
+impl GameRecord {
+    fn to_line(&self) -> String {
+        format!("{},{},{},{},{}", self.category, self.solved as u8, self.time.as_secs(), self.hints, self.puzzle)
+    }
+
+    fn parse(line: &str) -> Option<Self> {
+        let fields: Vec<&str> = line.split(',').collect();
+        let [category, solved, seconds, hints, puzzle] = fields[..] else {
+            return None;
+        };
+        Some(GameRecord {
+            category: category.to_string(),
+            solved: solved == "1",
+            time: Duration::from_secs(seconds.parse().ok()?),
+            hints: hints.parse().ok()?,
+            puzzle: puzzle.to_string(),
+        })
+    }
+}
+
+/// Summary of the games of a category.
+pub struct Stats {
+    pub played: usize,
+    pub solved: usize,
+    /// Fastest and average time of the games solved without hints.
+    pub best: Option<Duration>,
+    pub average: Option<Duration>,
+    pub hints: u32,
+}
+
+/// Puzzles the player has completed, identified by their givens, and the played games.
+/// Kept in the data directory as a .sdm file and a CSV file.
+#[derive(Default)]
+pub struct Records {
+    solved: HashSet<String>,
+    games: Vec<GameRecord>,
+}
+
+impl Records {
+    pub fn load() -> Self {
+        Records {
+            solved: storage::read_lines(SOLVED_FILE).into_iter().collect(),
+            games: storage::read_lines(GAMES_FILE).iter().filter_map(|line| GameRecord::parse(line)).collect(),
+        }
+    }
+
+    /// `puzzle` is the line of 81 digits of the givens.
+    pub fn is_solved(&self, puzzle: &str) -> bool {
+        self.solved.contains(puzzle)
+    }
+
+    pub fn mark_solved(&mut self, puzzle: &str) -> Result<(), String> {
+        if self.solved.insert(puzzle.to_string()) {
+            storage::append_line(SOLVED_FILE, puzzle)?;
+        }
+        Ok(())
+    }
+
+    /// Adds a game, solved games also mark their puzzle as solved.
+    pub fn add_game(&mut self, game: GameRecord) -> Result<(), String> {
+        storage::append_line(GAMES_FILE, &game.to_line())?;
+        if game.solved {
+            self.mark_solved(&game.puzzle)?;
+        }
+        self.games.push(game);
+        Ok(())
+    }
+
+    pub fn stats(&self, category: &str) -> Stats {
+        let games: Vec<&GameRecord> = self.games.iter().filter(|game| game.category == category).collect();
+        let clean: Vec<Duration> = games.iter().filter(|game| game.solved && game.hints == 0).map(|game| game.time).collect();
+        Stats {
+            played: games.len(),
+            solved: games.iter().filter(|game| game.solved).count(),
+            best: clean.iter().min().copied(),
+            average: (!clean.is_empty()).then(|| clean.iter().sum::<Duration>() / clean.len() as u32),
+            hints: games.iter().map(|game| game.hints).sum(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(category: &str, solved: bool, seconds: u64, hints: u32) -> GameRecord {
+        GameRecord {
+            category: category.to_string(),
+            solved,
+            time: Duration::from_secs(seconds),
+            hints,
+            puzzle: "0".repeat(81),
+        }
+    }
+
+    #[test]
+    fn game_line_round_trip() {
+        let line = game("Very Hard", true, 321, 2).to_line();
+        let parsed = GameRecord::parse(&line).unwrap();
+        assert_eq!(parsed.to_line(), line);
+        assert!(GameRecord::parse("Easy,1,x,0,").is_none());
+        assert!(GameRecord::parse("Easy,1,10").is_none());
+    }
+
+    #[test]
+    fn stats_of_category() {
+        let records = Records {
+            solved: HashSet::new(),
+            games: vec![
+                game("Easy", true, 100, 0),
+                game("Easy", true, 300, 0),
+                game("Easy", true, 50, 3),
+                game("Easy", false, 20, 1),
+                game("Hard", true, 10, 0),
+            ],
+        };
+        let stats = records.stats("Easy");
+        assert_eq!((stats.played, stats.solved, stats.hints), (4, 3, 4));
+        assert_eq!(stats.best, Some(Duration::from_secs(100)));
+        assert_eq!(stats.average, Some(Duration::from_secs(200)));
+        let custom = records.stats(CUSTOM);
+        assert_eq!((custom.played, custom.best), (0, None));
+    }
+}
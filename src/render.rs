@@ -0,0 +1,121 @@
+use nannou::color::Rgba8;
+use nannou::image::{Rgba, RgbaImage};
+use nannou::prelude::*;
+use nannou::text::{self, Font};
+
+use crate::board::{self, BoardOptions, Shape};
+use crate::sudoku::Sudoku;
+use crate::theme::Theme;
+
+/// Software renderer for the board, used where no window is available.
+pub struct Renderer {
+    font: Font,
+    image: RgbaImage,
+}
+
+impl Renderer {
+    pub fn new(width: u32, height: u32) -> Self {
+        Renderer {
+            font: text::font::default_notosans(),
+            image: RgbaImage::new(width, height),
+        }
+    }
+
+    /// Renders the sudoku centered in the image, with the same layout as the window.
+    pub fn render(&mut self, sudoku: &Sudoku, theme: &Theme, options: BoardOptions) -> &RgbaImage {
+        if let Some((_, font)) = &theme.font {
+            self.font = font.clone();
+        }
+        let size = self.image.width().min(self.image.height()) as f32 - 10.0;
+        let background = board::opaque(theme.background_color);
+        for pixel in self.image.pixels_mut() {
+            *pixel = Rgba([background.red, background.green, background.blue, 255]);
+        }
+        let mut shapes = board::shapes(sudoku, theme, size, options);
+        shapes.sort_by(|a, b| a.z().total_cmp(&b.z()));
+        for shape in shapes.iter() {
+            self.draw(shape);
+        }
+        &self.image
+    }
+
+    fn draw(&mut self, shape: &Shape) {
+        match shape {
+            Shape::Rect { center, size, color, .. } => {
+                let min = self.to_pixel(vec2(center.x - size.x / 2.0, center.y + size.y / 2.0));
+                let max = self.to_pixel(vec2(center.x + size.x / 2.0, center.y - size.y / 2.0));
+                for y in min.y.round() as i64..max.y.round() as i64 {
+                    for x in min.x.round() as i64..max.x.round() as i64 {
+                        self.blend(x, y, *color, 1.0);
+                    }
+                }
+            }
+            Shape::Line { start, end, weight, color, .. } => {
+                let (a, b) = (self.to_pixel(*start), self.to_pixel(*end));
+                let radius = weight / 2.0;
+                let (min, max) = (a.min(b) - radius - 1.0, a.max(b) + radius + 1.0);
+                for y in min.y.floor() as i64..=max.y.ceil() as i64 {
+                    for x in min.x.floor() as i64..=max.x.ceil() as i64 {
+                        let p = vec2(x as f32 + 0.5, y as f32 + 0.5);
+                        let coverage = (radius + 0.5 - distance_to_segment(p, a, b)).clamp(0.0, 1.0);
+                        self.blend(x, y, *color, coverage);
+                    }
+                }
+            }
+            Shape::Text { center, font_size, text, color, .. } => {
+                let scale = text::pt_to_scale(*font_size);
+                let metrics = self.font.v_metrics(scale);
+                let width = self
+                    .font
+                    .layout(text, scale, text::rt::point(0.0, 0.0))
+                    .last()
+                    .map(|g| g.position().x + g.unpositioned().h_metrics().advance_width)
+                    .unwrap_or(0.0);
+                let pixel = self.to_pixel(*center);
+                let origin = text::rt::point(
+                    pixel.x - width / 2.0,
+                    pixel.y + (metrics.ascent + metrics.descent) / 2.0,
+                );
+                let glyphs: Vec<_> = self.font.layout(text, scale, origin).collect();
+                for glyph in glyphs.iter() {
+                    if let Some(bounds) = glyph.pixel_bounding_box() {
+                        glyph.draw(|x, y, coverage| {
+                            self.blend(bounds.min.x as i64 + x as i64, bounds.min.y as i64 + y as i64, *color, coverage);
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    /// Converts board coordinates to image coordinates with y pointing down.
+    fn to_pixel(&self, point: Vec2) -> Vec2 {
+        vec2(
+            point.x + self.image.width() as f32 / 2.0,
+            self.image.height() as f32 / 2.0 - point.y,
+        )
+    }
+
+    fn blend(&mut self, x: i64, y: i64, color: Rgba8, coverage: f32) {
+        if x < 0 || y < 0 || x >= self.image.width() as i64 || y >= self.image.height() as i64 {
+            return;
+        }
+        let alpha = color.alpha as f32 / 255.0 * coverage;
+        let pixel = self.image.get_pixel_mut(x as u32, y as u32);
+        let source = [color.red, color.green, color.blue];
+        for (channel, source) in pixel.0.iter_mut().zip(source) {
+            *channel = (*channel as f32 * (1.0 - alpha) + source as f32 * alpha).round() as u8;
+        }
+    }
+}
+
+fn distance_to_segment(p: Vec2, a: Vec2, b: Vec2) -> f32 {
+    let ab = b - a;
+    let length_squared = ab.length_squared();
+    let t = if length_squared == 0.0 {
+        0.0
+    } else {
+        ((p - a).dot(ab) / length_squared).clamp(0.0, 1.0)
+    };
+    p.distance(a + ab * t)
+}
@@ -0,0 +1,399 @@
+use std::fmt::Write;
+
+use crate::dlx::House;
+use crate::sudoku::Tile;
+
+/// Formula in conjunctive normal form. Variables are numbered from 1, a negative
+/// literal is the negation of its variable, like in the DIMACS format.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cnf {
+    pub variables: usize,
+    pub clauses: Vec<Vec<i32>>,
+}
+
+/// Variable that is true if the tile holds the digit.
+pub fn variable(indx: usize, n: u8) -> i32 {
+    (indx * 9 + n as usize) as i32
+}
+
+/// Encodes the sudoku with the given houses. Filled tiles become unit clauses.
+pub fn encode(tiles: &[Tile; 81], houses: &[House]) -> Cnf {
+    let mut clauses = Vec::new();
+    let at_most_one = |clauses: &mut Vec<Vec<i32>>, literals: &[i32]| {
+        for (i, a) in literals.iter().enumerate() {
+            for b in literals[i + 1..].iter() {
+                clauses.push(vec![-a, -b]);
+            }
+        }
+    };
+    for indx in 0..81 {
+        let literals: Vec<i32> = (1..=9).map(|n| variable(indx, n)).collect();
+        clauses.push(literals.clone());
+        at_most_one(&mut clauses, &literals);
+    }
+    for house in houses.iter() {
+        for n in 1..=9 {
+            let literals: Vec<i32> = house.iter().map(|indx| variable(*indx, n)).collect();
+            clauses.push(literals.clone());
+            at_most_one(&mut clauses, &literals);
+        }
+    }
+    for (indx, tile) in tiles.iter().enumerate() {
+        if let Some(n) = tile.to_u16() {
+            clauses.push(vec![variable(indx, n as u8)]);
+        }
+    }
+    Cnf { variables: 729, clauses }
+}
+
+/// Digits of the tiles in a model of an encoded sudoku, 0 where no digit is set.
+pub fn decode(model: &[i32]) -> [u8; 81] {
+    let mut digits = [0; 81];
+    for literal in model.iter().filter(|l| **l > 0 && **l <= 729) {
+        let variable = *literal as usize - 1;
+        digits[variable / 9] = (variable % 9) as u8 + 1;
+    }
+    digits
+}
+
+impl Cnf {
+    pub fn to_dimacs(&self) -> String {
+        let mut dimacs = format!("p cnf {} {}\n", self.variables, self.clauses.len());
+        for clause in self.clauses.iter() {
+            for literal in clause.iter() {
+                write!(dimacs, "{} ", literal).unwrap();
+            }
+            dimacs.push_str("0\n");
+        }
+        dimacs
+    }
+
+    pub fn from_dimacs(dimacs: &str) -> Result<Self, String> {
+        let mut variables = None;
+        let mut clauses = Vec::new();
+        let mut clause = Vec::new();
+        for line in dimacs.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('c') || line.starts_with('%') {
+                continue;
+            }
+            if let Some(header) = line.strip_prefix("p cnf") {
+                let count = header.split_whitespace().next().ok_or("missing variable count")?;
+                variables = Some(count.parse().map_err(|_| format!("invalid header: {}", line))?);
+                continue;
+            }
+            let variables = variables.ok_or("clause before the header line `p cnf`")?;
+            for token in line.split_whitespace() {
+                match token.parse::<i32>().map_err(|_| format!("invalid literal: {}", token))? {
+                    0 => clauses.push(std::mem::take(&mut clause)),
+                    literal if literal.unsigned_abs() as usize > variables => {
+                        return Err(format!("literal {} out of range, the header declares {} variables", literal, variables));
+                    }
+                    literal => clause.push(literal),
+                }
+            }
+        }
+        if !clause.is_empty() {
+            clauses.push(clause);
+        }
+        let variables = variables.ok_or("missing header line `p cnf`")?;
+        Ok(Cnf { variables, clauses })
+    }
+}
+
+/// Reads the literals of a model in the output format of SAT solvers, with the
+/// assignment on lines starting with `v`. Plain lists of literals, as written by
+/// MiniSat after a `SAT` line, are accepted too.
+pub fn parse_model(output: &str) -> Result<Vec<i32>, String> {
+    let mut model = Vec::new();
+    for line in output.lines().map(str::trim) {
+        if line.contains("UNSAT") {
+            return Err("the formula is unsatisfiable".to_string());
+        }
+        if line.is_empty() || line.starts_with('c') || line.starts_with('s') || line == "SAT" {
+            continue;
+        }
+        for token in line.trim_start_matches('v').split_whitespace() {
+            match token.parse::<i32>().map_err(|_| format!("invalid literal: {}", token))? {
+                0 => (),
+                literal => model.push(literal),
+            }
+        }
+    }
+    Ok(model)
+}
+
+/// Literals are `2 * variable + sign` with variables counted from 0.
+type Lit = usize;
+
+fn to_lit(literal: i32) -> Lit {
+    (literal.unsigned_abs() as usize - 1) * 2 + (literal < 0) as usize
+}
+
+/// Small conflict driven clause learning solver with two watched literals,
+/// first UIP learning, activity based branching and restarts.
+pub struct Cdcl {
+    clauses: Vec<Vec<Lit>>,
+    watches: Vec<Vec<usize>>,
+    /// Value of every literal, `None` while its variable is unassigned.
+    values: Vec<Option<bool>>,
+    level: Vec<usize>,
+    reason: Vec<Option<usize>>,
+    activity: Vec<f64>,
+    increment: f64,
+    trail: Vec<Lit>,
+    trail_limits: Vec<usize>,
+    propagated: usize,
+    unsatisfiable: bool,
+    pub conflicts: u64,
+    pub decisions: u64,
+}
+
+impl Cdcl {
+    pub fn new(cnf: &Cnf) -> Self {
+        let variables = cnf.variables;
+        let mut solver = Cdcl {
+            clauses: Vec::new(),
+            watches: vec![Vec::new(); variables * 2],
+            values: vec![None; variables * 2],
+            level: vec![0; variables],
+            reason: vec![None; variables],
+            activity: vec![0.0; variables],
+            increment: 1.0,
+            trail: Vec::new(),
+            trail_limits: Vec::new(),
+            propagated: 0,
+            unsatisfiable: false,
+            conflicts: 0,
+            decisions: 0,
+        };
+        for clause in cnf.clauses.iter() {
+            let mut lits: Vec<Lit> = clause.iter().map(|l| to_lit(*l)).collect();
+            lits.sort_unstable();
+            lits.dedup();
+            if lits.windows(2).any(|w| w[0] ^ 1 == w[1]) {
+                continue;
+            }
+            solver.add_clause(lits);
+        }
+        solver
+    }
+
+    fn add_clause(&mut self, lits: Vec<Lit>) {
+        match lits.len() {
+            0 => self.unsatisfiable = true,
+            1 => match self.values[lits[0]] {
+                Some(false) => self.unsatisfiable = true,
+                Some(true) => (),
+                None => self.assign(lits[0], None),
+            },
+            _ => {
+                let index = self.clauses.len();
+                self.watches[lits[0] ^ 1].push(index);
+                self.watches[lits[1] ^ 1].push(index);
+                self.clauses.push(lits);
+            }
+        }
+    }
+
+    fn assign(&mut self, lit: Lit, reason: Option<usize>) {
+        self.values[lit] = Some(true);
+        self.values[lit ^ 1] = Some(false);
+        self.level[lit / 2] = self.trail_limits.len();
+        self.reason[lit / 2] = reason;
+        self.trail.push(lit);
+    }
+
+    /// Propagates all pending assignments, returns the index of a conflicting clause.
+    fn propagate(&mut self) -> Option<usize> {
+        while self.propagated < self.trail.len() {
+            let false_lit = self.trail[self.propagated] ^ 1;
+            self.propagated += 1;
+            let watching = std::mem::take(&mut self.watches[false_lit ^ 1]);
+            let mut kept = Vec::with_capacity(watching.len());
+            let mut conflict = None;
+            for (i, &index) in watching.iter().enumerate() {
+                if conflict.is_some() {
+                    kept.extend_from_slice(&watching[i..]);
+                    break;
+                }
+                let clause = &mut self.clauses[index];
+                if clause[0] == false_lit {
+                    clause.swap(0, 1);
+                }
+                if self.values[clause[0]] == Some(true) {
+                    kept.push(index);
+                    continue;
+                }
+                let replacement = (2..clause.len()).find(|k| self.values[clause[*k]] != Some(false));
+                if let Some(k) = replacement {
+                    clause.swap(1, k);
+                    self.watches[clause[1] ^ 1].push(index);
+                    continue;
+                }
+                kept.push(index);
+                let unit = clause[0];
+                match self.values[unit] {
+                    Some(false) => conflict = Some(index),
+                    _ => self.assign(unit, Some(index)),
+                }
+            }
+            self.watches[false_lit ^ 1] = kept;
+            if conflict.is_some() {
+                return conflict;
+            }
+        }
+        None
+    }
+
+    /// Derives the first UIP clause of the conflict and the level to jump back to.
+    fn analyze(&mut self, conflict: usize) -> (Vec<Lit>, usize) {
+        let level = self.trail_limits.len();
+        let mut seen = vec![false; self.level.len()];
+        let mut learnt = vec![0];
+        let mut pending = 0;
+        let mut clause = conflict;
+        let mut index = self.trail.len();
+        let mut uip;
+        loop {
+            let start = if pending == 0 && learnt.len() == 1 { 0 } else { 1 };
+            for k in start..self.clauses[clause].len() {
+                let lit = self.clauses[clause][k];
+                let variable = lit / 2;
+                if seen[variable] || self.level[variable] == 0 {
+                    continue;
+                }
+                seen[variable] = true;
+                self.bump(variable);
+                if self.level[variable] == level {
+                    pending += 1;
+                } else {
+                    learnt.push(lit);
+                }
+            }
+            loop {
+                index -= 1;
+                uip = self.trail[index];
+                if seen[uip / 2] {
+                    break;
+                }
+            }
+            pending -= 1;
+            if pending == 0 {
+                break;
+            }
+            clause = self.reason[uip / 2].unwrap();
+        }
+        learnt[0] = uip ^ 1;
+        let backjump = learnt[1..].iter().map(|lit| self.level[lit / 2]).max().unwrap_or(0);
+        if let Some(k) = (1..learnt.len()).find(|k| self.level[learnt[*k] / 2] == backjump) {
+            learnt.swap(1, k);
+        }
+        self.increment *= 1.05;
+        (learnt, backjump)
+    }
+
+    fn bump(&mut self, variable: usize) {
+        self.activity[variable] += self.increment;
+        if self.activity[variable] > 1e100 {
+            self.activity.iter_mut().for_each(|a| *a *= 1e-100);
+            self.increment *= 1e-100;
+        }
+    }
+
+    fn backtrack(&mut self, level: usize) {
+        if self.trail_limits.len() <= level {
+            return;
+        }
+        let start = self.trail_limits[level];
+        for lit in self.trail.drain(start..) {
+            self.values[lit] = None;
+            self.values[lit ^ 1] = None;
+            self.reason[lit / 2] = None;
+        }
+        self.trail_limits.truncate(level);
+        self.propagated = start;
+    }
+
+    fn decide(&mut self) -> Option<Lit> {
+        (0..self.level.len())
+            .filter(|v| self.values[v * 2].is_none())
+            .max_by(|a, b| self.activity[*a].total_cmp(&self.activity[*b]))
+            .map(|v| v * 2)
+    }
+
+    /// Searches a satisfying assignment and returns it as a list of literals.
+    pub fn solve(&mut self) -> Option<Vec<i32>> {
+        if self.unsatisfiable {
+            return None;
+        }
+        let mut restart_limit = 100.0;
+        let mut conflicts_since_restart = 0;
+        loop {
+            if let Some(conflict) = self.propagate() {
+                self.conflicts += 1;
+                conflicts_since_restart += 1;
+                if self.trail_limits.is_empty() {
+                    return None;
+                }
+                let (learnt, level) = self.analyze(conflict);
+                self.backtrack(level);
+                let lit = learnt[0];
+                if learnt.len() == 1 {
+                    self.assign(lit, None);
+                } else {
+                    let index = self.clauses.len();
+                    self.watches[learnt[0] ^ 1].push(index);
+                    self.watches[learnt[1] ^ 1].push(index);
+                    self.clauses.push(learnt);
+                    self.assign(lit, Some(index));
+                }
+                continue;
+            }
+            if conflicts_since_restart as f64 > restart_limit {
+                conflicts_since_restart = 0;
+                restart_limit *= 1.5;
+                self.backtrack(0);
+                continue;
+            }
+            match self.decide() {
+                Some(lit) => {
+                    self.decisions += 1;
+                    self.trail_limits.push(self.trail.len());
+                    self.assign(lit, None);
+                }
+                None => {
+                    let model = (0..self.level.len())
+                        .map(|v| if self.values[v * 2] == Some(true) { v as i32 + 1 } else { -(v as i32) - 1 })
+                        .collect();
+                    return Some(model);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dlx;
+    use crate::sudoku::Sudoku;
+    use crate::validate;
+
+    const PUZZLE: &str = "530070000600195000098000060800060003400803001700020006060000280000419005000080079";
+
+    #[test]
+    fn solves_encoded_sudoku() {
+        let givens = Sudoku::from_line(PUZZLE).unwrap().tiles;
+        let cnf = Cnf::from_dimacs(&encode(&givens, &dlx::standard_houses()).to_dimacs()).unwrap();
+        let model = Cdcl::new(&cnf).solve().expect("the puzzle has a solution");
+        assert_eq!(validate::solution(&givens, &decode(&model), &dlx::standard_houses()), Ok(()));
+    }
+
+    #[test]
+    fn rejects_malformed_dimacs() {
+        for dimacs in ["1 2 0", "p cnf x 1\n1 0", "p cnf 2 1\n1 5 0", "p cnf 2 1\n1 -3 0", "p cnf 2 1\n1 a 0"] {
+            assert!(Cnf::from_dimacs(dimacs).is_err(), "{}", dimacs);
+        }
+        assert_eq!(Cnf::from_dimacs("c comment\np cnf 2 2\n1 -2 0\n2 0").unwrap().clauses, vec![vec![1, -2], vec![2]]);
+    }
+}
@@ -0,0 +1,432 @@
+use crate::dlx::{self, DancingLinks};
+use crate::stochastic::Stochastic;
+use crate::sudoku::{SolverState, Sudoku, Tile};
+use crate::validate;
+
+/// Algorithm used by the animated solver.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SolverKind {
+    Backtracking,
+    DancingLinks,
+    Annealing,
+    Genetic,
+}
+
+impl SolverKind {
+    pub fn next(self) -> Self {
+        match self {
+            SolverKind::Backtracking => SolverKind::DancingLinks,
+            SolverKind::DancingLinks => SolverKind::Annealing,
+            SolverKind::Annealing => SolverKind::Genetic,
+            SolverKind::Genetic => SolverKind::Backtracking,
+        }
+    }
+
+    pub fn to_string(self) -> &'static str {
+        match self {
+            SolverKind::Backtracking => "Backtracking",
+            SolverKind::DancingLinks => "Dancing Links",
+            SolverKind::Annealing => "Simulated Annealing",
+            SolverKind::Genetic => "Genetic Algorithm",
+        }
+    }
+
+    /// True if the solver stops at breakpoints.
+    pub fn has_breakpoints(self) -> bool {
+        matches!(self, SolverKind::Backtracking | SolverKind::DancingLinks)
+    }
+
+    /// True if the solver records its steps, so they can be undone and scrubbed through.
+    pub fn has_history(self) -> bool {
+        self == SolverKind::Backtracking
+    }
+
+    /// Creates a solver of this kind for the grid. Solvers that make random choices make
+    /// the same ones for the same seed.
+    /// The backtracker keeps the last `trace_limit` steps to rewind through, none for 0.
+    pub fn create(self, tiles: &[Tile; 81], seed: u64, trace_limit: usize) -> Box<dyn Solver> {
+        match self {
+            SolverKind::Backtracking => Box::new(Backtracker::new(trace_limit)),
+            SolverKind::DancingLinks => Box::new(DancingLinks::new(tiles, &dlx::standard_houses())),
+            SolverKind::Annealing => Box::new(Stochastic::annealing(tiles, seed)),
+            SolverKind::Genetic => Box::new(Stochastic::genetic(tiles, seed)),
+        }
+    }
+}
+
+/// Counters of a solver run, shown in the panel and as the heatmap.
+#[derive(Clone)]
+pub struct SolverStats {
+    pub steps: u64,
+    /// Number of tiles currently filled by the solver.
+    pub depth: u64,
+    pub backtracks: u64,
+    /// Number of times a stochastic search started over because it got stuck.
+    pub restarts: u64,
+    /// Number of solver steps spent on each cell during the current run.
+    pub visits: [u32; 81],
+    /// Number of times the solver changed the value of each cell during the current run.
+    pub writes: [u32; 81],
+}
+
+impl SolverStats {
+    pub const NONE: SolverStats = SolverStats {
+        steps: 0,
+        depth: 0,
+        backtracks: 0,
+        restarts: 0,
+        visits: [0; 81],
+        writes: [0; 81],
+    };
+}
+
+impl Default for SolverStats {
+    fn default() -> Self {
+        SolverStats::NONE
+    }
+}
+
+/// Incremental solving strategy, advanced a few steps every frame. A solver works on the
+/// tiles of the grid, it keeps the filled tiles and writes its digits as `Tile::SolverVariable`.
+pub trait Solver: Send + Sync {
+    /// Performs up to `steps` steps. Stops early once the solver finished or when a step
+    /// hits one of the breakpoints, returns true in the latter case.
+    fn advance(&mut self, tiles: &mut [Tile; 81], steps: u32, breakpoints: &[Breakpoint]) -> bool;
+
+    /// `Running` until the solver found a solution or knows there is none.
+    fn state(&self) -> SolverState;
+
+    fn stats(&self) -> &SolverStats;
+
+    /// Tile the solver is working on.
+    fn active_cell(&self) -> Option<usize> {
+        None
+    }
+
+    /// Digits the solver still considers for an empty tile, bit n is set for digit n.
+    /// `None` if the solver only goes by the digits used in the row, column and box.
+    fn candidates(&self, _indx: usize) -> Option<u16> {
+        None
+    }
+
+    /// Additional lines for the panel.
+    fn status(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Recorded steps that can be undone and redone. Empty if the solver keeps no history.
+    fn history(&self) -> &[TraceStep] {
+        &[]
+    }
+
+    /// Number of steps before the first one in the history, which were dropped to keep
+    /// the history short.
+    fn history_start(&self) -> usize {
+        0
+    }
+
+    /// Moves to the given position in the history, which is the number of steps taken.
+    fn seek(&mut self, _tiles: &mut [Tile; 81], _position: usize) {}
+
+    fn boxed_clone(&self) -> Box<dyn Solver>;
+}
+
+impl Clone for Box<dyn Solver> {
+    fn clone(&self) -> Self {
+        self.boxed_clone()
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Direction {
+    Forward,
+    Backward,
+}
+
+/// A single recorded solver step. Stores enough to undo and redo the step.
+#[derive(Clone, Copy, Debug)]
+pub struct TraceStep {
+    pub cell: u8,
+    pub previous: Tile,
+    pub value: Tile,
+    pub direction: Direction,
+}
+
+impl TraceStep {
+    /// The index the solver moves to after this step.
+    pub fn next_indx(&self) -> usize {
+        let cell = self.cell as usize;
+        match self.value {
+            Tile::Empty if self.previous != Tile::Empty => cell.wrapping_sub(1),
+            _ if self.value != self.previous => cell,
+            _ => match self.direction {
+                Direction::Forward => cell + 1,
+                Direction::Backward => cell.wrapping_sub(1),
+            },
+        }
+    }
+
+    /// True if the step wrote a new solver value into its cell.
+    pub fn is_assignment(&self) -> bool {
+        matches!(self.value, Tile::SolverVariable(_)) && self.value != self.previous
+    }
+
+    /// True if the step gave up on its cell and moved back.
+    pub fn is_backtrack(&self) -> bool {
+        self.value == Tile::Empty && self.direction == Direction::Backward
+    }
+
+    /// Change in the number of solver-filled cells caused by this step.
+    pub fn depth_change(&self) -> i64 {
+        match (self.previous, self.value) {
+            (Tile::Empty, Tile::SolverVariable(_)) => 1,
+            (Tile::SolverVariable(_), Tile::Empty) => -1,
+            _ => 0,
+        }
+    }
+}
+
+/// Condition that pauses a running solver.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Breakpoint {
+    CellAssigned(usize),
+    DepthExceeds(u64),
+    Backtrack(u64),
+}
+
+impl Breakpoint {
+    pub const DEPTHS: [u64; 6] = [10, 20, 30, 40, 50, 60];
+    pub const BACKTRACKS: [u64; 6] = [1, 10, 100, 1000, 10000, 100000];
+
+    pub fn is_hit(&self, step: &TraceStep, depth: u64, backtracks: u64) -> bool {
+        match *self {
+            Breakpoint::CellAssigned(cell) => step.cell as usize == cell && step.is_assignment(),
+            Breakpoint::DepthExceeds(n) => step.depth_change() > 0 && depth == n + 1,
+            Breakpoint::Backtrack(n) => step.is_backtrack() && backtracks == n,
+        }
+    }
+}
+
+impl std::fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Breakpoint::CellAssigned(cell) => f.write_str(&validate::tile_name(*cell)),
+            Breakpoint::DepthExceeds(n) => write!(f, "depth > {}", n),
+            Breakpoint::Backtrack(n) => write!(f, "backtrack #{}", n),
+        }
+    }
+}
+
+/// Depth first search over the tiles in order. Tries the next available digit in the
+/// active tile and moves back once no digit is left. The last steps are recorded, so the
+/// search can be rewound and replayed.
+#[derive(Clone)]
+pub struct Backtracker {
+    active_indx: usize,
+    direction: Direction,
+    trace: Vec<TraceStep>,
+    /// Most steps kept in the trace, the oldest ones are dropped first.
+    trace_limit: usize,
+    /// Steps dropped from the front of the trace.
+    trace_start: usize,
+    /// Direction after the last dropped step.
+    start_direction: Direction,
+    stats: SolverStats,
+    finished: Option<SolverState>,
+}
+
+impl Default for Backtracker {
+    fn default() -> Self {
+        Backtracker::new(Backtracker::TRACE_LIMIT)
+    }
+}
+
+impl Backtracker {
+    /// Steps kept for rewinding by default, a few megabytes.
+    pub const TRACE_LIMIT: usize = 1 << 20;
+
+    pub fn new(trace_limit: usize) -> Self {
+        Backtracker {
+            active_indx: 0,
+            direction: Direction::Forward,
+            trace: Vec::new(),
+            trace_limit,
+            trace_start: 0,
+            start_direction: Direction::Forward,
+            stats: SolverStats::default(),
+            finished: None,
+        }
+    }
+
+    fn position(&self) -> usize {
+        self.stats.steps as usize
+    }
+
+    fn trace_end(&self) -> usize {
+        self.trace_start + self.trace.len()
+    }
+
+    /// Appends a step, dropping the oldest quarter of the trace once it is full.
+    fn record(&mut self, step: TraceStep) {
+        if self.trace_limit == 0 {
+            return;
+        }
+        if self.trace.len() >= self.trace_limit {
+            let dropped = self.trace.len().div_ceil(4);
+            self.start_direction = self.trace[dropped - 1].direction;
+            self.trace.drain(..dropped);
+            self.trace_start += dropped;
+        }
+        self.trace.push(step);
+    }
+
+    fn next_available_number(tiles: &[Tile; 81], indx: usize) -> Option<u8> {
+        let used = Sudoku::used_numbers(tiles, indx);
+        let current = tiles[indx].to_u16().unwrap_or(0);
+        (current + 1..=9).find(|i| used >> i & 1 == 0).map(|i| i as u8)
+    }
+
+    fn solution_possible(tiles: &[Tile; 81], indx: usize) -> bool {
+        Sudoku::squares_iter(indx)
+            .all(|tile| match tiles[tile] {
+                Tile::Empty => Sudoku::used_numbers(tiles, tile) != 0b1111111110,
+                _ => true,
+            })
+    }
+
+    fn redo(&mut self, tiles: &mut [Tile; 81]) -> TraceStep {
+        let step = self.trace[self.position() - self.trace_start];
+        tiles[step.cell as usize] = step.value;
+        self.active_indx = step.next_indx();
+        self.direction = step.direction;
+        self.count(&step);
+        step
+    }
+
+    fn undo(&mut self, tiles: &mut [Tile; 81]) {
+        let step = self.trace[self.position() - self.trace_start - 1];
+        tiles[step.cell as usize] = step.previous;
+        self.active_indx = step.cell as usize;
+        self.uncount(&step);
+        self.direction = match self.position() - self.trace_start {
+            0 => self.start_direction,
+            n => self.trace[n - 1].direction,
+        };
+    }
+
+    fn count(&mut self, step: &TraceStep) {
+        let stats = &mut self.stats;
+        stats.steps += 1;
+        stats.depth = stats.depth.wrapping_add_signed(step.depth_change());
+        stats.backtracks += step.is_backtrack() as u64;
+        stats.visits[step.cell as usize] += 1;
+        stats.writes[step.cell as usize] += (step.value != step.previous) as u32;
+    }
+
+    fn uncount(&mut self, step: &TraceStep) {
+        let stats = &mut self.stats;
+        stats.steps -= 1;
+        stats.depth = stats.depth.wrapping_add_signed(-step.depth_change());
+        stats.backtracks -= step.is_backtrack() as u64;
+        stats.visits[step.cell as usize] -= 1;
+        stats.writes[step.cell as usize] -= (step.value != step.previous) as u32;
+    }
+
+    /// Performs a single step. Redoes the recorded step if the position is behind the end of
+    /// the trace. Returns `None` once the search has finished.
+    fn step(&mut self, tiles: &mut [Tile; 81]) -> Option<TraceStep> {
+        if self.position() < self.trace_end() {
+            return Some(self.redo(tiles));
+        }
+        if self.active_indx >= 81 {
+            self.finished = Some(match tiles.contains(&Tile::Empty) {
+                false => SolverState::SolutionFound,
+                true => SolverState::NoSolution,
+            });
+            return None;
+        }
+        let cell = self.active_indx;
+        let previous = tiles[cell];
+        let next_number = Backtracker::next_available_number(tiles, cell);
+        match previous {
+            Tile::Const(_) | Tile::PlayerVariable(_) => match self.direction {
+                Direction::Forward => self.active_indx += 1,
+                Direction::Backward => self.active_indx = self.active_indx.wrapping_sub(1),
+            }
+            Tile::SolverVariable(_) if self.direction == Direction::Forward => {
+                self.active_indx += 1
+            }
+            Tile::SolverVariable(_) | Tile::Empty if next_number.is_some() => {
+                tiles[cell] = Tile::SolverVariable(next_number.unwrap());
+                match Backtracker::solution_possible(tiles, cell) {
+                    true => self.direction = Direction::Forward,
+                    false => self.direction = Direction::Backward,
+                }
+            }
+            _ => {
+                tiles[cell] = Tile::Empty;
+                self.active_indx = self.active_indx.wrapping_sub(1);
+                self.direction = Direction::Backward;
+            },
+        }
+        let step = TraceStep {
+            cell: cell as u8,
+            previous,
+            value: tiles[cell],
+            direction: self.direction,
+        };
+        self.record(step);
+        self.count(&step);
+        Some(step)
+    }
+}
+
+impl Solver for Backtracker {
+    fn advance(&mut self, tiles: &mut [Tile; 81], steps: u32, breakpoints: &[Breakpoint]) -> bool {
+        for _ in 0..steps {
+            match self.step(tiles) {
+                Some(step) if breakpoints.iter().any(|b| b.is_hit(&step, self.stats.depth, self.stats.backtracks)) => return true,
+                Some(_) => (),
+                None => return false,
+            }
+        }
+        false
+    }
+
+    fn state(&self) -> SolverState {
+        match self.finished {
+            Some(state) if self.position() >= self.trace_end() => state,
+            _ => SolverState::Running,
+        }
+    }
+
+    fn stats(&self) -> &SolverStats {
+        &self.stats
+    }
+
+    fn active_cell(&self) -> Option<usize> {
+        Some(self.active_indx).filter(|indx| *indx < 81)
+    }
+
+    fn history(&self) -> &[TraceStep] {
+        &self.trace
+    }
+
+    fn history_start(&self) -> usize {
+        self.trace_start
+    }
+
+    fn seek(&mut self, tiles: &mut [Tile; 81], position: usize) {
+        let position = position.clamp(self.trace_start, self.trace_end());
+        while self.position() > position {
+            self.undo(tiles);
+        }
+        while self.position() < position {
+            self.redo(tiles);
+        }
+    }
+
+    fn boxed_clone(&self) -> Box<dyn Solver> {
+        Box::new(self.clone())
+    }
+}
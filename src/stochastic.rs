@@ -0,0 +1,393 @@
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+use crate::bitboard::Bitboard;
+use crate::solver::{Breakpoint, Solver, SolverStats};
+use crate::sudoku::{SolverState, Tile};
+
+/// Tiles of every box, a candidate grid always holds each digit once per box.
+fn boxes() -> [[usize; 9]; 9] {
+    std::array::from_fn(|b| std::array::from_fn(|i| b / 3 * 27 + b % 3 * 3 + i / 3 * 9 + i % 3))
+}
+
+/// Number of missing digits in a row or column.
+fn line_cost(cells: &[u8; 81], line: impl Iterator<Item = usize>) -> u32 {
+    9 - line.fold(0u16, |used, indx| used | 1 << cells[indx]).count_ones()
+}
+
+fn row_cost(cells: &[u8; 81], row: usize) -> u32 {
+    line_cost(cells, (0..9).map(|i| row * 9 + i))
+}
+
+fn column_cost(cells: &[u8; 81], column: usize) -> u32 {
+    line_cost(cells, (0..9).map(|i| i * 9 + column))
+}
+
+/// Number of missing digits over all rows and columns, 0 for a solved grid.
+fn cost(cells: &[u8; 81]) -> u32 {
+    (0..9).map(|i| row_cost(cells, i) + column_cost(cells, i)).sum()
+}
+
+/// Filled tiles, which stay fixed, and the free tiles of every box, in the order of `boxes`.
+#[derive(Clone)]
+struct Givens {
+    cells: [u8; 81],
+    free: Vec<Vec<usize>>,
+}
+
+impl Givens {
+    /// Returns `None` if a box contains a digit twice.
+    fn new(tiles: &[Tile; 81]) -> Option<Self> {
+        let mut cells = [0; 81];
+        let mut free = Vec::new();
+        for house in boxes() {
+            let mut used = 0u16;
+            let mut tiles_free = Vec::new();
+            for indx in house {
+                match tiles[indx].to_u16() {
+                    Some(n) if used & 1 << n != 0 => return None,
+                    Some(n) => {
+                        used |= 1 << n;
+                        cells[indx] = n as u8;
+                    }
+                    None => tiles_free.push(indx),
+                }
+            }
+            free.push(tiles_free);
+        }
+        Some(Givens { cells, free })
+    }
+
+    /// Fills the free tiles of every box with its missing digits in random order.
+    fn random_fill(&self, rng: &mut StdRng) -> [u8; 81] {
+        let mut cells = self.cells;
+        for (house, free) in boxes().iter().zip(self.free.iter()) {
+            let used = house.iter().fold(0u16, |used, indx| used | 1 << cells[*indx]);
+            let mut missing: Vec<u8> = (1..=9).filter(|n| used & 1 << n == 0).collect();
+            missing.shuffle(rng);
+            for (indx, n) in free.iter().zip(missing) {
+                cells[*indx] = n;
+            }
+        }
+        cells
+    }
+
+    /// Swaps two random free tiles of a random box and returns them, `None` if no box has two free tiles.
+    fn random_swap(&self, cells: &mut [u8; 81], rng: &mut StdRng) -> Option<(usize, usize)> {
+        let swappable: Vec<&Vec<usize>> = self.free.iter().filter(|free| free.len() >= 2).collect();
+        let free = swappable.choose(rng)?;
+        let pair: Vec<&usize> = free.choose_multiple(rng, 2).collect();
+        cells.swap(*pair[0], *pair[1]);
+        Some((*pair[0], *pair[1]))
+    }
+}
+
+/// Cost of the rows and columns of two tiles.
+fn swap_cost(cells: &[u8; 81], a: usize, b: usize) -> u32 {
+    let rows = if a / 9 == b / 9 { 0 } else { row_cost(cells, a / 9) + row_cost(cells, b / 9) };
+    let columns = if a % 9 == b % 9 { 0 } else { column_cost(cells, a % 9) + column_cost(cells, b % 9) };
+    rows + columns
+}
+
+/// Simulated annealing on grids that hold every digit once per box. A step swaps two
+/// free tiles of a box and keeps the swap if it lowers the number of conflicts, or,
+/// with a probability that shrinks with the temperature, even if it does not.
+#[derive(Clone)]
+pub struct Annealing {
+    givens: Givens,
+    pub cells: [u8; 81],
+    pub cost: u32,
+    pub temperature: f64,
+    best_cost: u32,
+    /// Steps since the best cost last improved, the search reheats when it gets stuck.
+    stuck: u32,
+    pub reheats: u32,
+    rng: StdRng,
+}
+
+impl Annealing {
+    const INITIAL_TEMPERATURE: f64 = 1.0;
+    const COOLING: f64 = 0.9999;
+    const REHEAT_AFTER: u32 = 30_000;
+    /// The search gives up after this many reheats, the puzzle probably has no solution.
+    const GIVE_UP_AFTER: u32 = 200;
+
+    /// Returns `None` if a box of the grid contains a digit twice. The same seed gives the same search.
+    pub fn new(tiles: &[Tile; 81], seed: u64) -> Option<Self> {
+        let givens = Givens::new(tiles)?;
+        let mut rng = StdRng::seed_from_u64(seed);
+        let cells = givens.random_fill(&mut rng);
+        Some(Annealing {
+            givens,
+            cells,
+            cost: cost(&cells),
+            temperature: Annealing::INITIAL_TEMPERATURE,
+            best_cost: u32::MAX,
+            stuck: 0,
+            reheats: 0,
+            rng,
+        })
+    }
+
+    /// Tries a swap, counting both tiles as visited and as written if the swap is kept.
+    pub fn step(&mut self, stats: &mut SolverStats) {
+        let before = self.cells;
+        let Some((a, b)) = self.givens.random_swap(&mut self.cells, &mut self.rng) else {
+            return;
+        };
+        stats.visits[a] += 1;
+        stats.visits[b] += 1;
+        let delta = swap_cost(&self.cells, a, b) as f64 - swap_cost(&before, a, b) as f64;
+        if delta <= 0.0 || self.rng.gen::<f64>() < (-delta / self.temperature).exp() {
+            self.cost = (self.cost as f64 + delta) as u32;
+            stats.writes[a] += 1;
+            stats.writes[b] += 1;
+        } else {
+            self.cells = before;
+        }
+        self.temperature *= Annealing::COOLING;
+        if self.cost < self.best_cost {
+            self.best_cost = self.cost;
+            self.stuck = 0;
+        } else {
+            self.stuck += 1;
+        }
+        if self.stuck > Annealing::REHEAT_AFTER {
+            self.temperature = Annealing::INITIAL_TEMPERATURE;
+            self.best_cost = u32::MAX;
+            self.stuck = 0;
+            self.reheats += 1;
+        }
+    }
+}
+
+/// Genetic algorithm on grids that hold every digit once per box. Every generation
+/// keeps the fittest grids and breeds the rest by taking each box from one of two
+/// parents picked in small tournaments, followed by random swaps within boxes.
+#[derive(Clone)]
+pub struct Genetic {
+    givens: Givens,
+    /// Grids and their cost, sorted with the fittest first.
+    population: Vec<([u8; 81], u32)>,
+    best_cost: u32,
+    /// Generations since the best cost last improved, the population is renewed when it gets stuck.
+    stuck: u32,
+    pub renewals: u32,
+    rng: StdRng,
+}
+
+impl Genetic {
+    const POPULATION: usize = 150;
+    const ELITE: usize = 8;
+    const MUTATION_RATE: f64 = 0.8;
+    const RENEW_AFTER: u32 = 100;
+    /// The search gives up after this many renewals, the puzzle probably has no solution.
+    const GIVE_UP_AFTER: u32 = 100;
+
+    /// Returns `None` if a box of the grid contains a digit twice. The same seed gives the same search.
+    pub fn new(tiles: &[Tile; 81], seed: u64) -> Option<Self> {
+        let givens = Givens::new(tiles)?;
+        let mut genetic = Genetic {
+            givens,
+            population: Vec::new(),
+            best_cost: u32::MAX,
+            stuck: 0,
+            renewals: 0,
+            rng: StdRng::seed_from_u64(seed),
+        };
+        genetic.renew();
+        Some(genetic)
+    }
+
+    /// Starts over with random grids.
+    fn renew(&mut self) {
+        self.population.clear();
+        self.best_cost = u32::MAX;
+        while self.population.len() < Genetic::POPULATION {
+            let cells = self.givens.random_fill(&mut self.rng);
+            self.population.push((cells, cost(&cells)));
+        }
+        self.population.sort_by_key(|(_, cost)| *cost);
+    }
+
+    /// Fittest grid of the current generation.
+    pub fn best(&self) -> &[u8; 81] {
+        &self.population[0].0
+    }
+
+    pub fn cost(&self) -> u32 {
+        self.population[0].1
+    }
+
+    fn tournament(&mut self) -> usize {
+        (0..3).map(|_| self.rng.gen_range(0..self.population.len())).min().unwrap()
+    }
+
+    /// Breeds the next generation. The tiles of mutations count as visited, the tiles the
+    /// fittest grid changes as written.
+    pub fn step(&mut self, stats: &mut SolverStats) {
+        let best = *self.best();
+        let mut next: Vec<([u8; 81], u32)> = self.population[..Genetic::ELITE].to_vec();
+        while next.len() < Genetic::POPULATION {
+            let (a, b) = (self.tournament(), self.tournament());
+            let mut child = self.population[a].0;
+            for free in self.givens.free.iter() {
+                if self.rng.gen_bool(0.5) {
+                    for indx in free {
+                        child[*indx] = self.population[b].0[*indx];
+                    }
+                }
+            }
+            while self.rng.gen_bool(Genetic::MUTATION_RATE) {
+                let before = child;
+                // Mutations that add conflicts are mostly undone, which keeps the population from drifting.
+                if let Some((a, b)) = self.givens.random_swap(&mut child, &mut self.rng) {
+                    stats.visits[a] += 1;
+                    stats.visits[b] += 1;
+                    if swap_cost(&child, a, b) > swap_cost(&before, a, b) && self.rng.gen_bool(0.9) {
+                        child = before;
+                    }
+                }
+            }
+            next.push((child, cost(&child)));
+        }
+        next.sort_by_key(|(_, cost)| *cost);
+        self.population = next;
+
+        if self.cost() < self.best_cost {
+            self.best_cost = self.cost();
+            self.stuck = 0;
+        } else {
+            self.stuck += 1;
+        }
+        if self.stuck > Genetic::RENEW_AFTER {
+            self.renew();
+            self.stuck = 0;
+            self.renewals += 1;
+        }
+        for (indx, (before, after)) in best.iter().zip(self.best()).enumerate() {
+            stats.writes[indx] += (before != after) as u32;
+        }
+    }
+}
+
+#[derive(Clone)]
+enum Search {
+    Annealing(Annealing),
+    Genetic(Genetic),
+}
+
+impl Search {
+    /// Most steps done in one call of `advance`, about ten milliseconds of work, so fast
+    /// speeds do not stall the drawing.
+    fn steps_per_call(&self) -> u32 {
+        match self {
+            Search::Annealing(_) => 50_000,
+            Search::Genetic(_) => 50,
+        }
+    }
+
+    fn gave_up(&self) -> bool {
+        match self {
+            Search::Annealing(annealing) => annealing.reheats >= Annealing::GIVE_UP_AFTER,
+            Search::Genetic(genetic) => genetic.renewals >= Genetic::GIVE_UP_AFTER,
+        }
+    }
+}
+
+/// Simulated annealing or the genetic algorithm as an animated solver. Shows the current
+/// grid of the annealing or the fittest grid of the genetic algorithm. The search finishes
+/// once it found a solution, or gives up after restarting too often without one.
+#[derive(Clone)]
+pub struct Stochastic {
+    /// `None` if the filled tiles already conflict.
+    search: Option<Search>,
+    stats: SolverStats,
+}
+
+impl Stochastic {
+    pub fn annealing(tiles: &[Tile; 81], seed: u64) -> Self {
+        Stochastic::new(tiles, |tiles| Annealing::new(tiles, seed).map(Search::Annealing))
+    }
+
+    pub fn genetic(tiles: &[Tile; 81], seed: u64) -> Self {
+        Stochastic::new(tiles, |tiles| Genetic::new(tiles, seed).map(Search::Genetic))
+    }
+
+    fn new(tiles: &[Tile; 81], search: impl FnOnce(&[Tile; 81]) -> Option<Search>) -> Self {
+        Stochastic {
+            search: Bitboard::new(tiles).and_then(|_| search(tiles)),
+            stats: SolverStats::default(),
+        }
+    }
+
+    /// Number of digits missing from the rows and columns of the shown grid.
+    fn cost(&self) -> Option<u32> {
+        match self.search.as_ref()? {
+            Search::Annealing(annealing) => Some(annealing.cost),
+            Search::Genetic(genetic) => Some(genetic.cost()),
+        }
+    }
+}
+
+impl Solver for Stochastic {
+    fn advance(&mut self, tiles: &mut [Tile; 81], steps: u32, _breakpoints: &[Breakpoint]) -> bool {
+        let Some(search) = self.search.as_mut() else {
+            return false;
+        };
+        for _ in 0..steps.min(search.steps_per_call()) {
+            if search.gave_up() {
+                break;
+            }
+            match search {
+                Search::Annealing(annealing) if annealing.cost > 0 => annealing.step(&mut self.stats),
+                Search::Genetic(genetic) if genetic.cost() > 0 => genetic.step(&mut self.stats),
+                _ => break,
+            }
+            self.stats.steps += 1;
+        }
+        let (cells, restarts) = match search {
+            Search::Annealing(annealing) => (&annealing.cells, annealing.reheats),
+            Search::Genetic(genetic) => (genetic.best(), genetic.renewals),
+        };
+        self.stats.restarts = restarts as u64;
+        for (indx, n) in cells.iter().enumerate() {
+            if let Tile::Empty | Tile::SolverVariable(_) = tiles[indx] {
+                tiles[indx] = Tile::SolverVariable(*n);
+            }
+        }
+        false
+    }
+
+    fn state(&self) -> SolverState {
+        match self.cost() {
+            None => SolverState::NoSolution,
+            Some(0) => SolverState::SolutionFound,
+            Some(_) if self.search.as_ref().is_some_and(Search::gave_up) => SolverState::GaveUp,
+            Some(_) => SolverState::Running,
+        }
+    }
+
+    fn stats(&self) -> &SolverStats {
+        &self.stats
+    }
+
+    fn status(&self) -> Vec<String> {
+        match (&self.search, self.cost()) {
+            (Some(Search::Annealing(annealing)), Some(cost)) => vec![
+                format!("Cost: {}  Temperature: {:.4}", cost, annealing.temperature),
+                format!("Reheats: {} / {}", annealing.reheats, Annealing::GIVE_UP_AFTER),
+            ],
+            (Some(Search::Genetic(genetic)), Some(cost)) => vec![
+                format!("Cost: {}", cost),
+                format!("Renewals: {} / {}", genetic.renewals, Genetic::GIVE_UP_AFTER),
+            ],
+            _ => Vec::new(),
+        }
+    }
+
+    fn boxed_clone(&self) -> Box<dyn Solver> {
+        Box::new(self.clone())
+    }
+}
@@ -0,0 +1,121 @@
+use std::collections::HashSet;
+use std::ffi::OsString;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Directory of the files kept between sessions, like the solved puzzles and imported
+/// collections. `NANNOU_SUDOKU_DATA` replaces the default of the platform.
+pub fn data_dir() -> PathBuf {
+    platform_dir("NANNOU_SUDOKU_DATA", "XDG_DATA_HOME", ".local/share")
+}
+
+/// Directory of the settings, like the key bindings. `NANNOU_SUDOKU_CONFIG` replaces the
+/// default of the platform.
+pub fn config_dir() -> PathBuf {
+    platform_dir("NANNOU_SUDOKU_CONFIG", "XDG_CONFIG_HOME", ".config")
+}
+
+/// `override_var` if set, otherwise the application directory in the per-user directory of
+/// the platform, which is given by `xdg_var` or `xdg_default` in the home on Linux. Tests
+/// use a temporary directory instead, so they never change the files of the player.
+fn platform_dir(override_var: &str, xdg_var: &str, xdg_default: &str) -> PathBuf {
+    if cfg!(test) {
+        return std::env::temp_dir().join("nannou-sudoku-test");
+    }
+    if let Some(dir) = std::env::var_os(override_var) {
+        return dir.into();
+    }
+    let var = |name| std::env::var_os(name).filter(|value| !value.is_empty()).map(PathBuf::from);
+    let base = if cfg!(windows) {
+        var("APPDATA")
+    } else if cfg!(target_os = "macos") {
+        var("HOME").map(|home| home.join("Library/Application Support"))
+    } else {
+        var(xdg_var).or_else(|| var("HOME").map(|home| home.join(xdg_default)))
+    };
+    base.unwrap_or_default().join("nannou-sudoku")
+}
+
+/// Lines of a file in the data directory, none if the file does not exist yet.
+pub fn read_lines(name: &str) -> Vec<String> {
+    std::fs::read_to_string(data_dir().join(name))
+        .map(|content| content.lines().map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+/// Appends a line to a file in the data directory, creating both if needed.
+pub fn append_line(name: &str, line: &str) -> Result<(), String> {
+    let dir = data_dir();
+    std::fs::create_dir_all(&dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    let path = dir.join(name);
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| writeln!(file, "{}", line))
+        .map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// Writes a file in the config directory, creating the directory if needed. The content
+/// goes to a temporary file first, which then replaces the file, so a crash or a full disk
+/// never leaves it half written.
+pub fn write_config(name: &str, content: &str) -> Result<(), String> {
+    let dir = config_dir();
+    std::fs::create_dir_all(&dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    let path = dir.join(name);
+    let temporary = dir.join(format!("{}.tmp", name));
+    std::fs::write(&temporary, content)
+        .and_then(|_| std::fs::rename(&temporary, &path))
+        .map_err(|e| {
+            _ = std::fs::remove_file(&temporary);
+            format!("{}: {}", path.display(), e)
+        })
+}
+
+/// A path below `dir` named after the current second. While a file or directory of that
+/// name, with any extension, exists there `-2`, `-3` and so on are appended, so exports
+/// and recordings of the same second do not overwrite each other.
+pub fn timestamped_path(dir: &Path) -> PathBuf {
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+    let taken: HashSet<OsString> = std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| Path::new(&entry.file_name()).file_stem().map(|stem| stem.to_os_string()))
+        .collect();
+    let name = (1..)
+        .map(|n| match n {
+            1 => timestamp.to_string(),
+            n => format!("{}-{}", timestamp, n),
+        })
+        .find(|name| !taken.contains(&OsString::from(name)))
+        .unwrap();
+    dir.join(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timestamped_paths_do_not_repeat() {
+        let dir = std::env::temp_dir().join(format!("nannou-sudoku-storage-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let first = timestamped_path(&dir);
+        std::fs::write(first.with_extension("svg"), "").unwrap();
+        let second = timestamped_path(&dir);
+        std::fs::create_dir(&second).unwrap();
+        let third = timestamped_path(&dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_ne!(first, second);
+        assert_ne!(second, third);
+        assert_ne!(first, third);
+    }
+
+    #[test]
+    fn tests_keep_away_from_the_player_files() {
+        assert!(data_dir().starts_with(std::env::temp_dir()));
+        assert!(config_dir().starts_with(std::env::temp_dir()));
+    }
+}
@@ -1,16 +1,20 @@
+use std::fmt;
+use std::str::FromStr;
+
+use rand::rngs::StdRng;
 use rand::seq::IteratorRandom;
+use rand::SeedableRng;
+
+use crate::bitboard::Bitboard;
+use crate::dlx;
+use crate::solver::{Backtracker, Breakpoint, Solver, SolverKind, SolverStats, TraceStep};
+use crate::validate;
 
 const EASY: &str = include_str!("../input/Sudoku_easy.sdm");
 const MEDIUM: &str = include_str!("../input/Sudoku_medium.sdm");
 const HARD: &str = include_str!("../input/Sudoku_hard.sdm");
 const VERY_HARD: &str = include_str!("../input/Top_50K_Toughest.sdm");
 
-#[derive(PartialEq, Eq, Clone, Copy)]
-pub enum Direction {
-    Forward,
-    Backward,
-}
-
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Tile {
     Empty,
@@ -20,10 +24,10 @@ pub enum Tile {
 }
 
 impl Tile {
-    pub fn to_u16(&self) -> Option<u16> {
+    pub fn to_u16(self) -> Option<u16> {
         match self {
             Tile::Empty => None,
-            Tile::SolverVariable(n) | Tile::PlayerVariable(n) | Tile::Const(n) => Some(*n as u16),
+            Tile::SolverVariable(n) | Tile::PlayerVariable(n) | Tile::Const(n) => Some(n as u16),
         }
     }
 }
@@ -37,7 +41,34 @@ pub enum Difficulty {
 }
 
 impl Difficulty {
-    pub fn to_string(&self) -> &str {
+    pub const ALL: [Difficulty; 4] = [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard, Difficulty::VeryHard];
+
+    /// Difficulty of the bundled collection with the name, like in puzzle IDs.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Difficulty::ALL.into_iter().find(|difficulty| difficulty.collection() == name)
+    }
+
+    /// Name of the bundled collection in puzzle IDs.
+    pub fn collection(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Medium => "medium",
+            Difficulty::Hard => "hard",
+            Difficulty::VeryHard => "toughest",
+        }
+    }
+
+    /// The bundled collection, one puzzle per line.
+    pub fn puzzles(self) -> &'static str {
+        match self {
+            Difficulty::Easy => EASY,
+            Difficulty::Medium => MEDIUM,
+            Difficulty::Hard => HARD,
+            Difficulty::VeryHard => VERY_HARD,
+        }
+    }
+
+    pub fn to_string(self) -> &'static str {
         match self {
             Difficulty::Easy => "Easy",
             Difficulty::Medium => "Medium",
@@ -65,21 +96,62 @@ impl Difficulty {
     }
 }
 
+/// A puzzle of the bundled collections, written like `hard:1234` with the line starting at 1.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PuzzleId {
+    pub difficulty: Difficulty,
+    pub line: usize,
+}
+
+impl PuzzleId {
+    /// The puzzle as a line of 81 digits, `None` if the collection has no such line.
+    pub fn puzzle(self) -> Option<&'static str> {
+        self.difficulty.puzzles().lines().nth(self.line.checked_sub(1)?)
+    }
+}
+
+impl fmt::Display for PuzzleId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.difficulty.collection(), self.line)
+    }
+}
+
+impl FromStr for PuzzleId {
+    type Err = String;
+
+    fn from_str(id: &str) -> Result<Self, String> {
+        let (collection, line) = id.split_once(':').ok_or(format!("a puzzle ID looks like hard:1234: {}", id))?;
+        let difficulty = Difficulty::from_name(collection)
+            .ok_or(format!("unknown collection {}, use easy, medium, hard or toughest", collection))?;
+        let line = line.parse().map_err(|_| format!("invalid line in puzzle ID: {}", id))?;
+        let id = PuzzleId { difficulty, line };
+        match id.puzzle() {
+            Some(_) => Ok(id),
+            None => Err(format!("{} has no line {}", collection, line)),
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 pub enum SolverState {
     Idle,
     Running,
+    Paused,
     SolutionFound,
     NoSolution,
+    /// A stochastic solver stopped searching without finding a solution.
+    GaveUp,
 }
 
 impl SolverState {
-    pub fn to_string(&self) -> &str {
+    pub fn to_string(self) -> &'static str {
         match self {
             SolverState::Running => "Running",
             SolverState::Idle => "Idle",
+            SolverState::Paused => "Paused",
             SolverState::SolutionFound => "Solution Found",
             SolverState::NoSolution => "No Solution",
+            SolverState::GaveUp => "Gave Up",
         }
     }
 }
@@ -88,11 +160,20 @@ impl SolverState {
 pub struct Sudoku {
     pub tiles: [Tile; 81],
     pub difficulty: Difficulty,
-    pub active_indx: usize,
     pub state: SolverState,
-    pub step_count: u64,
     pub real_steps_per_frame: f32,
-    direction: Direction,
+    pub breakpoints: Vec<Breakpoint>,
+    /// Algorithm used for the next solver run.
+    pub solver: SolverKind,
+    /// Most steps of a run kept for rewinding, 0 to keep none.
+    pub trace_limit: usize,
+    /// Solver of the current run, created when the run starts.
+    active_solver: Option<Box<dyn Solver>>,
+    /// Where the grid was loaded from, `None` for puzzles entered or given as digits.
+    pub id: Option<PuzzleId>,
+    /// Seed of the random puzzle selection and of the stochastic solvers.
+    pub seed: u64,
+    rng: StdRng,
     steps_per_frame: f32,
     substeps: u8,
 }
@@ -102,12 +183,16 @@ impl Default for Sudoku {
     fn default() -> Self {
         Sudoku {
             tiles: [Tile::Empty; 81],
-            active_indx: 0,
-            direction: Direction::Forward,
             state: SolverState::Idle,
-            step_count: 0,
             steps_per_frame: 1.0,
             real_steps_per_frame: 1.0,
+            breakpoints: Vec::new(),
+            solver: SolverKind::Backtracking,
+            trace_limit: Backtracker::TRACE_LIMIT,
+            active_solver: None,
+            id: None,
+            seed: 0,
+            rng: StdRng::seed_from_u64(0),
             substeps: 0,
             difficulty: Difficulty::Medium,
         }
@@ -115,33 +200,34 @@ impl Default for Sudoku {
 }
 
 impl Sudoku {
-    pub fn next_available_number(&self, indx: usize) -> Option<u8> {
-        let available = self.avaliable_numbers(indx);
-        let current = self.tiles[indx].to_u16().unwrap_or(0);
-        for i in current + 1..=9 {
-            if available >> i & 1 == 0 {
-                return Some(i as u8);
-            }
-        }
-        None
-    }
-
     fn is_available(&self, indx: usize, n: u8) -> bool {
         let available = self.avaliable_numbers(indx);
         available >> n & 1 == 0
     }
 
     pub fn avaliable_numbers(&self, indx: usize) -> u16 {
+        Sudoku::used_numbers(&self.tiles, indx)
+    }
+
+    /// Digits used in the row, column and box of the tile, bit n is set for digit n.
+    pub fn used_numbers(tiles: &[Tile; 81], indx: usize) -> u16 {
         Sudoku::squares_iter(indx)
-            .filter_map(|i| self.tiles[i].to_u16())
+            .filter_map(|i| tiles[i].to_u16())
             .fold(0, |acc, n| acc | 1 << n)
     }
 
+    /// Digits that can still go into an empty tile, bit n is set for digit n. Asks the
+    /// running solver, if it keeps track of candidates.
+    pub fn candidates(&self, indx: usize) -> u16 {
+        self.active_solver
+            .as_ref()
+            .and_then(|solver| solver.candidates(indx))
+            .unwrap_or(!self.avaliable_numbers(indx) & 0b1111111110)
+    }
+
     pub fn reset_solver(&mut self) {
-        self.active_indx = 0;
-        self.step_count = 0;
-        self.direction = Direction::Forward;
         self.state = SolverState::Idle;
+        self.active_solver = None;
     }
 
     pub fn clear_variables(&mut self) {
@@ -152,22 +238,56 @@ impl Sudoku {
         }
     }
 
+    /// Restarts the random puzzle selection, the same seed loads the same puzzles in the same order.
+    pub fn reseed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
     /// Loads a random Sudoku from the included list of sudokus
     pub fn load_random(&mut self) {
-        let list = match self.difficulty {
-            Difficulty::Easy => EASY,
-            Difficulty::Medium => MEDIUM,
-            Difficulty::Hard => HARD,
-            Difficulty::VeryHard => VERY_HARD,
-        };
-        let random_line = list.lines().choose(&mut rand::thread_rng()).unwrap();
-        *self = self.from_line(random_line);
+        let (indx, line) = self.difficulty.puzzles().lines().enumerate().choose(&mut self.rng).unwrap();
+        self.load_line(line);
+        self.id = Some(PuzzleId { difficulty: self.difficulty, line: indx + 1 });
+    }
+
+    /// Loads a puzzle of the bundled collections and selects its difficulty.
+    pub fn load_id(&mut self, id: PuzzleId) {
+        self.load_line(id.puzzle().expect("puzzle IDs are checked when they are parsed"));
+        self.difficulty = id.difficulty;
+        self.id = Some(id);
+    }
+
+    /// Solves the grid at once with the bitboard solver. Player entries are kept fixed,
+    /// like in the animated solver.
+    pub fn solve_instantly(&mut self) {
+        self.clear_variables();
+        self.reset_solver();
+        match Bitboard::new(&self.tiles).and_then(|mut board| board.solve()) {
+            Some(solution) => {
+                for (tile, n) in self.tiles.iter_mut().zip(solution) {
+                    if *tile == Tile::Empty {
+                        *tile = Tile::SolverVariable(n);
+                    }
+                }
+                self.state = SolverState::SolutionFound;
+            }
+            None => self.state = SolverState::NoSolution,
+        }
     }
 
     /// Changes the number of steps per frame by a multiplier.
-    /// Steps per frame is clamped between 0.01 and 100000.
     pub fn change_steps_per_frame(&mut self, mult: f32) {
-        self.steps_per_frame = (self.steps_per_frame * mult).max(0.005).min(100000.0);
+        self.set_steps_per_frame(self.steps_per_frame * mult);
+    }
+
+    pub fn steps_per_frame(&self) -> f32 {
+        self.steps_per_frame
+    }
+
+    /// Steps per frame is clamped between 0.005 and 100000.
+    pub fn set_steps_per_frame(&mut self, steps: f32) {
+        self.steps_per_frame = steps.clamp(0.005, 100000.0);
         if self.steps_per_frame < 1.0 {
             self.real_steps_per_frame = 1.0 / ((1.0 / self.steps_per_frame).floor() + 1.0);
         } else {
@@ -175,31 +295,184 @@ impl Sudoku {
         }
     }
 
-    fn from_line(&self, line: &str) -> Self {
-        Sudoku {
-            tiles: line
-                .chars()
-                .map(|c| match c {
-                    '1'..='9' => Tile::Const(c.to_digit(10).unwrap() as u8),
-                    '0' => Tile::Empty,
-                    _ => panic!("Invalid character in input"),
-                })
-                .collect::<Vec<Tile>>()
-                .try_into()
-                .unwrap(),
-            active_indx: 0,
-            direction: Direction::Forward,
-            state: SolverState::Idle,
-            step_count: 0,
-            substeps: 0,
-            ..*self
+    /// Puzzle given as a line of 81 digits, 0 for empty tiles.
+    pub fn from_line(line: &str) -> Result<Sudoku, String> {
+        if line.len() != 81 || !line.chars().all(|c| c.is_ascii_digit()) {
+            return Err(format!("a puzzle must consist of 81 digits: {}", line));
         }
+        let mut sudoku = Sudoku::default();
+        sudoku.load_line(line);
+        Ok(sudoku)
+    }
+
+    /// The grid as a line of 81 digits, 0 for empty tiles. Digits of the player and the
+    /// solver are written like givens.
+    pub fn to_line(&self) -> String {
+        self.to_digits().iter().map(|n| n.to_string()).collect()
+    }
+
+    /// The givens as a line of 81 digits, identifies the puzzle.
+    pub fn givens_line(&self) -> String {
+        self.tiles.iter().map(|tile| match tile {
+            Tile::Const(n) => char::from(b'0' + n),
+            _ => '0',
+        }).collect()
+    }
+
+    /// True if the player filled every tile without breaking a rule.
+    pub fn is_completed(&self) -> bool {
+        let by_player = self.tiles.iter().all(|tile| matches!(tile, Tile::Const(_) | Tile::PlayerVariable(_)));
+        by_player && validate::solution(&self.tiles, &self.to_digits(), &dlx::standard_houses()).is_ok()
+    }
+
+    /// Digit of every tile, 0 for empty tiles.
+    pub fn to_digits(&self) -> [u8; 81] {
+        self.tiles.map(|tile| tile.to_u16().unwrap_or(0) as u8)
+    }
+
+    /// Replaces the grid with the one given as a line of 81 digits and resets the solver.
+    pub fn load_line(&mut self, line: &str) {
+        self.tiles = line
+            .chars()
+            .map(|c| match c {
+                '1'..='9' => Tile::Const(c.to_digit(10).unwrap() as u8),
+                '0' => Tile::Empty,
+                _ => panic!("Invalid character in input"),
+            })
+            .collect::<Vec<Tile>>()
+            .try_into()
+            .unwrap();
+        self.substeps = 0;
+        self.id = None;
+        self.reset_solver();
     }
 
     pub fn is_running(&self) -> bool {
         matches!(self.state, SolverState::Running)
     }
 
+    /// True while a solver run is in progress, including when it is paused.
+    pub fn is_active(&self) -> bool {
+        matches!(self.state, SolverState::Running | SolverState::Paused)
+    }
+
+    /// Toggles between running and paused without losing the solver state.
+    pub fn toggle_pause(&mut self) {
+        self.state = match self.state {
+            SolverState::Running => SolverState::Paused,
+            SolverState::Paused => SolverState::Running,
+            state => state,
+        };
+    }
+
+    /// Counters of the current solver run.
+    pub fn stats(&self) -> &SolverStats {
+        self.active_solver.as_ref().map_or(&SolverStats::NONE, |solver| solver.stats())
+    }
+
+    /// Tile the solver is working on.
+    pub fn active_cell(&self) -> Option<usize> {
+        self.active_solver.as_ref().and_then(|solver| solver.active_cell())
+    }
+
+    /// Panel lines of the running solver.
+    pub fn solver_status(&self) -> Vec<String> {
+        self.active_solver.as_ref().map_or(Vec::new(), |solver| solver.status())
+    }
+
+    /// Recorded steps of the current run.
+    pub fn history(&self) -> &[TraceStep] {
+        self.active_solver.as_ref().map_or(&[], |solver| solver.history())
+    }
+
+    /// Steps of the current run before the first recorded one.
+    pub fn history_start(&self) -> usize {
+        self.active_solver.as_ref().map_or(0, |solver| solver.history_start())
+    }
+
+    /// Position of the solver within the recorded trace.
+    pub fn trace_position(&self) -> usize {
+        self.stats().steps as usize
+    }
+
+    fn pause_for_history(&mut self) {
+        if !self.history().is_empty() {
+            self.state = SolverState::Paused;
+        }
+    }
+
+    /// Undoes the last recorded step.
+    pub fn step_back(&mut self) {
+        if self.trace_position() > self.history_start() {
+            self.seek(self.trace_position() - 1);
+        }
+    }
+
+    /// Advances the solver by a single step and pauses it.
+    /// Redoes a recorded step if the current position is behind the end of the trace.
+    pub fn step_forward(&mut self) {
+        match self.state {
+            SolverState::Idle => self.clear_variables(),
+            SolverState::SolutionFound | SolverState::NoSolution | SolverState::GaveUp
+                if self.trace_position() >= self.history_start() + self.history().len() => return,
+            _ => (),
+        }
+        self.state = SolverState::Paused;
+        self.advance(1);
+    }
+
+    /// Adds or removes a breakpoint on the assignment of the given cell.
+    pub fn toggle_cell_breakpoint(&mut self, cell: usize) {
+        let breakpoint = Breakpoint::CellAssigned(cell);
+        match self.breakpoints.iter().position(|b| *b == breakpoint) {
+            Some(i) => _ = self.breakpoints.remove(i),
+            None => self.breakpoints.push(breakpoint),
+        }
+    }
+
+    /// Cycles the depth breakpoint through a few thresholds and off.
+    pub fn cycle_depth_breakpoint(&mut self) {
+        let current = self.breakpoints.iter().find_map(|b| match b {
+            Breakpoint::DepthExceeds(n) => Some(*n),
+            _ => None,
+        });
+        self.breakpoints.retain(|b| !matches!(b, Breakpoint::DepthExceeds(_)));
+        if let Some(n) = Sudoku::next_threshold(&Breakpoint::DEPTHS, current) {
+            self.breakpoints.push(Breakpoint::DepthExceeds(n));
+        }
+    }
+
+    /// Cycles the backtrack breakpoint through a few counts and off.
+    pub fn cycle_backtrack_breakpoint(&mut self) {
+        let current = self.breakpoints.iter().find_map(|b| match b {
+            Breakpoint::Backtrack(n) => Some(*n),
+            _ => None,
+        });
+        self.breakpoints.retain(|b| !matches!(b, Breakpoint::Backtrack(_)));
+        if let Some(n) = Sudoku::next_threshold(&Breakpoint::BACKTRACKS, current) {
+            self.breakpoints.push(Breakpoint::Backtrack(n));
+        }
+    }
+
+    fn next_threshold(thresholds: &[u64], current: Option<u64>) -> Option<u64> {
+        match current {
+            None => thresholds.first().copied(),
+            Some(n) => thresholds.iter().copied().find(|t| *t > n),
+        }
+    }
+
+    /// Moves to the given position in the recorded trace.
+    pub fn seek(&mut self, position: usize) {
+        let Some(solver) = self.active_solver.as_mut() else {
+            return;
+        };
+        if solver.history().is_empty() {
+            return;
+        }
+        solver.seek(&mut self.tiles, position);
+        self.pause_for_history();
+    }
+
     pub fn try_insert(&mut self, indx: usize, tile: Tile) {
         if let Tile::Const(_) = self.tiles[indx] {
             return;
@@ -211,7 +484,8 @@ impl Sudoku {
         }
     }
 
-    fn get_steps(&mut self) -> u32 {
+    /// Number of steps to take in this frame. Below one step per frame, returns 1 every few frames.
+    pub fn get_steps(&mut self) -> u32 {
         if self.steps_per_frame < 1.0 {
             if self.substeps < (1.0 / self.steps_per_frame) as u8 {
                 self.substeps += 1;
@@ -236,47 +510,249 @@ impl Sudoku {
             .flat_map(move |f| (0..9).map(move |i| f(indx, i)))
     }
 
-    fn solution_possible(&self) -> bool {
-        Sudoku::squares_iter(self.active_indx)
-            .all(|tile| match self.tiles[tile] {
-                Tile::Empty => self.avaliable_numbers(tile) != 0b1111111110,
-                _ => true,
-            })
+    pub fn step(&mut self) {
+        let steps = self.get_steps();
+        self.advance(steps);
     }
 
-    pub fn step(&mut self) {
-        for _ in 0..self.get_steps() {
-            if self.active_indx >= 81 {
-                self.state = if !self.tiles.contains(&Tile::Empty) {
-                    SolverState::SolutionFound
-                } else {
-                    SolverState::NoSolution
+    /// Advances the solver of the current run, which is created on the first step.
+    /// Pauses at breakpoints and stores the result once the solver has finished.
+    pub fn advance(&mut self, steps: u32) {
+        let tiles = self.tiles;
+        let seed = self.seed;
+        let trace_limit = self.trace_limit;
+        let solver = self.active_solver.get_or_insert_with(|| self.solver.create(&tiles, seed, trace_limit));
+        if solver.advance(&mut self.tiles, steps, &self.breakpoints) {
+            self.state = SolverState::Paused;
+        }
+        match solver.state() {
+            SolverState::Running => (),
+            state => self.state = state,
+        }
+    }
+
+    /// How many of each digit are still missing from the grid, index 0 for digit 1.
+    pub fn remaining(&self) -> [usize; 9] {
+        let mut remaining = [9usize; 9];
+        for n in self.tiles.iter().filter_map(|tile| tile.to_u16()) {
+            remaining[n as usize - 1] = remaining[n as usize - 1].saturating_sub(1);
+        }
+        remaining
+    }
+
+    /// Tiles whose digit appears a second time in the same row, column or box.
+    pub fn conflicts(&self) -> [bool; 81] {
+        std::array::from_fn(|indx| match self.tiles[indx].to_u16() {
+            Some(n) => Sudoku::squares_iter(indx).any(|i| i != indx && self.tiles[i].to_u16() == Some(n)),
+            None => false,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::*;
+
+    /// A few puzzles spread over each bundled collection.
+    fn samples() -> impl Iterator<Item = &'static str> {
+        [EASY, MEDIUM, HARD, VERY_HARD].into_iter().flat_map(|list| list.lines().step_by(10_000).take(5))
+    }
+
+    fn solve_with(kind: SolverKind) {
+        for line in samples() {
+            let mut sudoku = Sudoku::from_line(line).unwrap();
+            sudoku.solver = kind;
+            sudoku.change_steps_per_frame(f32::MAX);
+            sudoku.state = SolverState::Running;
+            while sudoku.is_running() {
+                sudoku.step();
+            }
+            assert!(matches!(sudoku.state, SolverState::SolutionFound), "{} found no solution for {}", kind.to_string(), line);
+            let givens = Sudoku::from_line(line).unwrap().tiles;
+            assert_eq!(validate::solution(&givens, &sudoku.to_digits(), &dlx::standard_houses()), Ok(()), "{}", line);
+        }
+    }
+
+    #[test]
+    fn remaining_digits() {
+        let mut sudoku = Sudoku::default();
+        assert_eq!(sudoku.remaining(), [9; 9]);
+        sudoku.tiles[0] = Tile::Const(3);
+        sudoku.tiles[1] = Tile::PlayerVariable(3);
+        sudoku.tiles[2] = Tile::SolverVariable(9);
+        assert_eq!(sudoku.remaining(), [9, 9, 7, 9, 9, 9, 9, 9, 8]);
+    }
+
+    #[test]
+    fn backtracking_solves_samples() {
+        solve_with(SolverKind::Backtracking);
+    }
+
+    #[test]
+    fn rewinds_within_trace_limit() {
+        let mut sudoku = Sudoku::from_line(samples().nth(10).unwrap()).unwrap();
+        sudoku.trace_limit = 100;
+        sudoku.advance(1000);
+        assert!(sudoku.history().len() <= 100);
+        assert_eq!(sudoku.history_start() + sudoku.history().len(), 1000);
+        let tiles = sudoku.tiles;
+        sudoku.seek(0);
+        assert_eq!(sudoku.trace_position(), sudoku.history_start());
+        sudoku.seek(1000);
+        assert_eq!(sudoku.tiles, tiles);
+    }
+
+    #[test]
+    fn dancing_links_solves_samples() {
+        solve_with(SolverKind::DancingLinks);
+    }
+
+    #[test]
+    fn dancing_links_stops_at_breakpoints() {
+        let mut sudoku = Sudoku::from_line(samples().nth(10).unwrap()).unwrap();
+        let cell = sudoku.tiles.iter().rposition(|tile| *tile == Tile::Empty).unwrap();
+        sudoku.solver = SolverKind::DancingLinks;
+        sudoku.state = SolverState::Running;
+        sudoku.breakpoints.push(Breakpoint::CellAssigned(cell));
+        sudoku.advance(u32::MAX);
+        assert!(matches!(sudoku.state, SolverState::Paused));
+        assert!(matches!(sudoku.tiles[cell], Tile::SolverVariable(_)));
+        sudoku.clear_variables();
+        sudoku.reset_solver();
+        sudoku.breakpoints = vec![Breakpoint::DepthExceeds(10)];
+        sudoku.state = SolverState::Running;
+        sudoku.advance(u32::MAX);
+        assert!(matches!(sudoku.state, SolverState::Paused));
+        assert_eq!(sudoku.stats().depth, 11);
+    }
+
+    #[test]
+    fn solve_instantly_solves_samples() {
+        for line in samples() {
+            let mut sudoku = Sudoku::from_line(line).unwrap();
+            sudoku.solve_instantly();
+            assert!(matches!(sudoku.state, SolverState::SolutionFound));
+            let givens = Sudoku::from_line(line).unwrap().tiles;
+            assert_eq!(validate::solution(&givens, &sudoku.to_digits(), &dlx::standard_houses()), Ok(()), "{}", line);
+        }
+    }
+
+    #[test]
+    fn try_insert_never_creates_conflicts() {
+        let mut rng = StdRng::seed_from_u64(39);
+        for line in samples() {
+            let mut sudoku = Sudoku::from_line(line).unwrap();
+            let givens = sudoku.tiles;
+            for _ in 0..2000 {
+                let tile = match rng.gen_range(0..=9) {
+                    0 => Tile::Empty,
+                    n => Tile::PlayerVariable(n),
                 };
-                return;
+                sudoku.try_insert(rng.gen_range(0..81), tile);
+                assert!(!sudoku.conflicts().contains(&true), "conflict in {}", sudoku.to_line());
             }
-            self.step_count += 1;
-            let next_number = self.next_available_number(self.active_indx);
-            match self.tiles[self.active_indx] {
-                Tile::Const(_) | Tile::PlayerVariable(_) => match self.direction {
-                    Direction::Forward => self.active_indx += 1,
-                    Direction::Backward => self.active_indx -= 1,
-                }
-                Tile::SolverVariable(_) if self.direction == Direction::Forward => {
-                    self.active_indx += 1
+            for (tile, given) in sudoku.tiles.iter().zip(givens.iter()) {
+                if let Tile::Const(_) = given {
+                    assert_eq!(tile, given);
                 }
-                Tile::SolverVariable(_) | Tile::Empty if next_number.is_some() => {
-                    self.tiles[self.active_indx] = Tile::SolverVariable(next_number.unwrap());
-                    match self.solution_possible() {
-                        true => self.direction = Direction::Forward,
-                        false => self.direction = Direction::Backward,
-                    }
-                }
-                _ => {
-                    self.tiles[self.active_indx] = Tile::Empty;
-                    self.active_indx -= 1;
-                    self.direction = Direction::Backward;
-                },
             }
         }
     }
+
+    #[test]
+    fn line_round_trip() {
+        for line in samples() {
+            assert_eq!(Sudoku::from_line(line).unwrap().to_line(), line);
+        }
+        let mut sudoku = Sudoku::from_line(samples().next().unwrap()).unwrap();
+        sudoku.solve_instantly();
+        let solved = Sudoku::from_line(&sudoku.to_line()).unwrap();
+        assert_eq!(solved.to_digits(), sudoku.to_digits());
+        assert!(solved.tiles.iter().all(|tile| matches!(tile, Tile::Const(_))));
+    }
+
+    #[test]
+    fn same_seed_loads_same_puzzles() {
+        let load = |seed| {
+            let mut sudoku = Sudoku::default();
+            sudoku.reseed(seed);
+            (0..5).map(|_| { sudoku.load_random(); sudoku.id.unwrap() }).collect::<Vec<_>>()
+        };
+        assert_eq!(load(40), load(40));
+        assert_ne!(load(40), load(41));
+    }
+
+    #[test]
+    fn same_seed_gives_same_stochastic_run() {
+        let run = |seed| {
+            let mut sudoku = Sudoku::from_line(samples().next().unwrap()).unwrap();
+            sudoku.reseed(seed);
+            sudoku.solver = SolverKind::Genetic;
+            sudoku.advance(20);
+            sudoku.to_line()
+        };
+        assert_eq!(run(40), run(40));
+        assert_ne!(run(40), run(41));
+    }
+
+    #[test]
+    fn every_solver_fills_the_heatmap() {
+        for kind in [SolverKind::Backtracking, SolverKind::DancingLinks, SolverKind::Annealing, SolverKind::Genetic] {
+            let mut sudoku = Sudoku::from_line(samples().next().unwrap()).unwrap();
+            sudoku.solver = kind;
+            sudoku.advance(50);
+            let stats = sudoku.stats();
+            assert!(stats.visits.iter().any(|visits| *visits > 0), "{} counted no visits", kind.to_string());
+            assert!(stats.writes.iter().any(|writes| *writes > 0), "{} counted no writes", kind.to_string());
+            for indx in (0..81).filter(|indx| matches!(sudoku.tiles[*indx], Tile::Const(_))) {
+                assert_eq!(stats.writes[indx], 0, "{} wrote the given in tile {}", kind.to_string(), indx);
+            }
+        }
+    }
+
+    #[test]
+    fn puzzle_id_round_trip() {
+        let mut sudoku = Sudoku::default();
+        for difficulty in Difficulty::ALL {
+            sudoku.difficulty = difficulty;
+            sudoku.load_random();
+            let id = sudoku.id.unwrap();
+            let mut loaded = Sudoku::default();
+            loaded.load_id(id.to_string().parse().unwrap());
+            assert_eq!(loaded.to_line(), sudoku.to_line());
+            assert_eq!(loaded.id, Some(id));
+        }
+        assert_eq!("hard:1".parse::<PuzzleId>().unwrap().puzzle(), HARD.lines().next());
+        for id in ["hard", "hard:0", "hard:50001", "hard:x", "tough:1"] {
+            assert!(id.parse::<PuzzleId>().is_err(), "{}", id);
+        }
+    }
+
+    #[test]
+    fn completed_by_player() {
+        let line = samples().next().unwrap();
+        let mut sudoku = Sudoku::from_line(line).unwrap();
+        let mut solved = sudoku.clone();
+        solved.solve_instantly();
+        assert!(!solved.is_completed());
+        for (indx, tile) in solved.tiles.iter().enumerate() {
+            if let Tile::SolverVariable(n) = tile {
+                assert!(!sudoku.is_completed());
+                sudoku.try_insert(indx, Tile::PlayerVariable(*n));
+            }
+        }
+        assert!(sudoku.is_completed());
+        assert_eq!(sudoku.givens_line(), line);
+    }
+
+    #[test]
+    fn from_line_rejects_invalid_lines() {
+        let line = samples().next().unwrap();
+        assert!(Sudoku::from_line(&line[1..]).is_err());
+        assert!(Sudoku::from_line(&format!("{}0", line)).is_err());
+        assert!(Sudoku::from_line(&line.replacen('0', ".", 1)).is_err());
+    }
 }
@@ -1,70 +1,334 @@
-use nannou::{color, prelude::*};
-
-#[derive(Clone, Copy, Default)]
-pub enum ThemeType {
-    Light,
-    #[default]
-    Dark,
-    Discord,
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+use nannou::{color, prelude::*, text::Font};
+
+use crate::config::{self, parse, parse_lines};
+use crate::storage;
+
+/// Directory of the theme files in the config directory.
+const THEMES_DIRECTORY: &str = "themes";
+/// Time between checks of the themes directory for changed files.
+const RELOAD_INTERVAL: Duration = Duration::from_millis(500);
+
+pub type Color = rgb::Rgb<color::encoding::Srgb, u8>;
+
+/// Colors and font of the window, the recordings and the exports.
+#[derive(Clone)]
+pub struct Theme {
+    pub name: String,
+    pub primary_color: Color,
+    pub secondary_color: Color,
+    pub tile_color: Color,
+    pub background_color: Color,
+    pub theme_alpha: u8,
+    pub heat_low: Color,
+    pub heat_high: Color,
+    pub given_color: Color,
+    pub player_color: Color,
+    pub solver_color: Color,
+    /// Selected tile and the cursors of the browser and the calendar.
+    pub highlight_color: Color,
+    /// Tiles that break a rule.
+    pub conflict_color: Color,
+    /// Tile the solver works on.
+    pub active_color: Color,
+    /// Font of all texts, nannou's default font if `None`. The file is relative to the
+    /// themes directory.
+    pub font: Option<(String, Font)>,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::builtin("Dark").unwrap()
+    }
 }
 
-impl ThemeType {
-    pub fn next(&self) -> Self {
-        match self {
-            ThemeType::Dark => ThemeType::Discord,
-            ThemeType::Discord => ThemeType::Light,
-            ThemeType::Light => ThemeType::Dark,
+impl Theme {
+    /// Names of the themes built into the application, in the order `T` cycles them. The
+    /// colorblind themes use the Okabe-Ito palette and a viridis heatmap, which stay
+    /// distinguishable with the common kinds of color blindness.
+    pub const BUILTIN: [&'static str; 6] = ["Dark", "Discord", "Light", "High Contrast", "Colorblind Dark", "Colorblind Light"];
+
+    pub fn builtin(name: &str) -> Option<Self> {
+        let name = Theme::BUILTIN.into_iter().find(|builtin| builtin.eq_ignore_ascii_case(name))?;
+        let rgb = |(red, green, blue)| color::Rgb8::from_components((red, green, blue));
+        let palette = |primary: Color, secondary: Color, tile, background, alpha, heat_low, heat_high| Theme {
+            name: name.to_string(),
+            primary_color: primary,
+            secondary_color: secondary,
+            tile_color: tile,
+            background_color: background,
+            theme_alpha: alpha,
+            heat_low,
+            heat_high,
+            given_color: primary,
+            player_color: primary,
+            solver_color: secondary,
+            highlight_color: primary,
+            conflict_color: rgb((220, 50, 50)),
+            active_color: rgb((255, 0, 0)),
+            font: None,
+        };
+        let (viridis_low, viridis_high) = (rgb((68, 1, 84)), rgb((253, 231, 37)));
+        Some(match name {
+            "Light" => palette(color::BLACK, rgb((120, 120, 120)), rgb((242, 243, 245)), rgb((245, 245, 245)), 60, rgb((255, 214, 102)), rgb((214, 40, 40))),
+            "Dark" => palette(color::WHITE, color::GREY, color::BLACK, color::BLACK, 12, rgb((40, 60, 160)), rgb((255, 80, 40))),
+            "Discord" => palette(rgb((242, 243, 245)), rgb((181, 186, 193)), rgb((43, 45, 49)), rgb((30, 31, 34)), 20, rgb((88, 101, 242)), rgb((237, 66, 69))),
+            "High Contrast" => Theme {
+                player_color: rgb((255, 235, 0)),
+                solver_color: rgb((0, 230, 255)),
+                highlight_color: rgb((255, 235, 0)),
+                conflict_color: rgb((255, 0, 255)),
+                active_color: rgb((255, 140, 0)),
+                ..palette(color::WHITE, rgb((200, 200, 200)), color::BLACK, color::BLACK, 70, rgb((0, 90, 255)), rgb((255, 235, 0)))
+            },
+            "Colorblind Dark" => Theme {
+                player_color: rgb((86, 180, 233)),
+                solver_color: rgb((230, 159, 0)),
+                highlight_color: rgb((86, 180, 233)),
+                conflict_color: rgb((213, 94, 0)),
+                active_color: rgb((0, 158, 115)),
+                ..palette(color::WHITE, rgb((160, 160, 160)), rgb((20, 20, 20)), rgb((10, 10, 10)), 30, viridis_low, viridis_high)
+            },
+            _ => Theme {
+                player_color: rgb((0, 114, 178)),
+                solver_color: rgb((204, 121, 167)),
+                highlight_color: rgb((0, 114, 178)),
+                conflict_color: rgb((213, 94, 0)),
+                active_color: rgb((0, 158, 115)),
+                ..palette(color::BLACK, rgb((100, 100, 100)), rgb((250, 250, 250)), color::WHITE, 50, viridis_low, viridis_high)
+            },
+        })
+    }
+
+    /// Parses a theme file, lines like `primary = "#ffffff"`. Colors that are not given are
+    /// taken from `base`. For a new theme, whose base is not a built-in theme, the digit and
+    /// highlight colors follow the primary and secondary color of the file instead.
+    pub fn parse(base: Theme, content: &str, directory: &Path) -> (Self, Vec<String>) {
+        let is_new = Theme::builtin(&base.name).is_none();
+        let mut theme = base;
+        let (mut given, mut player, mut solver, mut highlight) = (None, None, None, None);
+        let problems = parse_lines(content, |key, value| {
+            match key {
+                "name" => theme.name = value.to_string(),
+                "primary" => theme.primary_color = parse_color(value)?,
+                "secondary" => theme.secondary_color = parse_color(value)?,
+                "tile" => theme.tile_color = parse_color(value)?,
+                "background" => theme.background_color = parse_color(value)?,
+                "alpha" => theme.theme_alpha = parse(value)?,
+                "heat_low" => theme.heat_low = parse_color(value)?,
+                "heat_high" => theme.heat_high = parse_color(value)?,
+                "given" => given = Some(parse_color(value)?),
+                "player" => player = Some(parse_color(value)?),
+                "solver" => solver = Some(parse_color(value)?),
+                "highlight" => highlight = Some(parse_color(value)?),
+                "conflict" => theme.conflict_color = parse_color(value)?,
+                "active" => theme.active_color = parse_color(value)?,
+                "font" => {
+                    let font = nannou::text::font::from_file(directory.join(value)).map_err(|e| format!("{}: {}", value, e))?;
+                    theme.font = Some((value.to_string(), font));
+                }
+                _ => return Err(format!("unknown setting {}", key)),
+            }
+            Ok(())
+        });
+        if is_new {
+            theme.given_color = given.unwrap_or(theme.primary_color);
+            theme.player_color = player.unwrap_or(theme.primary_color);
+            theme.solver_color = solver.unwrap_or(theme.secondary_color);
+            theme.highlight_color = highlight.unwrap_or(theme.primary_color);
+        } else {
+            theme.given_color = given.unwrap_or(theme.given_color);
+            theme.player_color = player.unwrap_or(theme.player_color);
+            theme.solver_color = solver.unwrap_or(theme.solver_color);
+            theme.highlight_color = highlight.unwrap_or(theme.highlight_color);
         }
+        (theme, problems)
     }
 
-    pub fn to_string(&self) -> &str {
-        match self {
-            ThemeType::Light => "Light",
-            ThemeType::Dark => "Dark",
-            ThemeType::Discord => "Discord",
+    /// The theme as the content of a theme file.
+    pub fn to_toml(&self) -> String {
+        let colors = [
+            ("primary", self.primary_color),
+            ("secondary", self.secondary_color),
+            ("tile", self.tile_color),
+            ("background", self.background_color),
+            ("heat_low", self.heat_low),
+            ("heat_high", self.heat_high),
+            ("given", self.given_color),
+            ("player", self.player_color),
+            ("solver", self.solver_color),
+            ("highlight", self.highlight_color),
+            ("conflict", self.conflict_color),
+            ("active", self.active_color),
+        ];
+        let mut content = format!("# Theme of nannou-sudoku, colors like \"#rrggbb\".\nname = \"{}\"\n", self.name);
+        for (key, color) in colors {
+            content.push_str(&format!("{} = \"#{:02x}{:02x}{:02x}\"\n", key, color.red, color.green, color.blue));
         }
+        content.push_str(&format!("# Opacity of the shaded tiles, 0 to 255.\nalpha = {}\n", self.theme_alpha));
+        match &self.font {
+            Some((file, _)) => content.push_str(&format!("font = \"{}\"\n", file)),
+            None => content.push_str("# TrueType font, relative to the themes directory.\n# font = \"fonts/font.ttf\"\n"),
+        }
+        content
+    }
+
+    /// Color of the heatmap ramp at `t` between 0 (cold) and 1 (hot).
+    pub fn heat_color(&self, t: f32) -> color::Rgba8 {
+        let t = t.clamp(0.0, 1.0);
+        let lerp = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t) as u8;
+        color::rgba(
+            lerp(self.heat_low.red, self.heat_high.red),
+            lerp(self.heat_low.green, self.heat_high.green),
+            lerp(self.heat_low.blue, self.heat_high.blue),
+            (60.0 + 160.0 * t) as u8,
+        )
     }
 }
 
-#[derive(Default)]
-pub struct Theme {
-    pub theme_type: ThemeType,
-    pub primary_color: rgb::Rgb<color::encoding::Srgb, u8>,
-    pub secondary_color: rgb::Rgb<color::encoding::Srgb, u8>,
-    pub tile_color: rgb::Rgb<color::encoding::Srgb, u8>,
-    pub background_color: rgb::Rgb<color::encoding::Srgb, u8>,
-    pub theme_alpha: u8,
+/// Color written like `#1e1f22`.
+fn parse_color(value: &str) -> Result<Color, String> {
+    let hex = value.strip_prefix('#').filter(|hex| hex.len() == 6 && hex.is_ascii()).ok_or(config::invalid(value))?;
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| config::invalid(value));
+    Ok(color::Rgb8::from_components((channel(0)?, channel(2)?, channel(4)?)))
 }
 
-impl Theme {
-    pub fn update(&mut self, theme: ThemeType) {
-        self.theme_type = theme;
-        match theme {
-            ThemeType::Light => {
-                self.primary_color = color::BLACK;
-                self.secondary_color = color::Rgb8::from_components((120, 120, 120));
-                self.tile_color = color::Rgb8::from_components((242, 243, 245));
-                self.background_color = color::Rgb8::from_components((245, 245, 245));
-                self.theme_alpha = 60;
+/// The built-in themes and the `.toml` files of the themes directory, which is watched for
+/// changes. A file with the name of a built-in theme replaces it.
+pub struct Themes {
+    pub themes: Vec<Theme>,
+    /// Theme files and their modification times when they were loaded.
+    files: Vec<(PathBuf, SystemTime)>,
+    checked: Instant,
+}
+
+impl Default for Themes {
+    /// Only the built-in themes.
+    fn default() -> Self {
+        Themes {
+            themes: Theme::BUILTIN.into_iter().filter_map(Theme::builtin).collect(),
+            files: Vec::new(),
+            checked: Instant::now(),
+        }
+    }
+}
+
+impl Themes {
+    /// Loads the theme files, problems with them are printed.
+    pub fn load() -> Self {
+        let files = Themes::scan();
+        let mut themes = Themes::default().themes;
+        for (path, _) in files.iter() {
+            let Ok(content) = std::fs::read_to_string(path) else {
+                continue;
+            };
+            let name = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+            let directory = path.parent().unwrap();
+            let (mut theme, mut problems) = Theme::parse(Theme { name, ..Theme::default() }, &content, directory);
+            // A file replacing a built-in theme only needs the colors it changes.
+            if let Some(builtin) = Theme::builtin(&theme.name) {
+                (theme, problems) = Theme::parse(builtin, &content, directory);
             }
-            ThemeType::Dark => {
-                self.primary_color = color::WHITE;
-                self.secondary_color = color::GREY;
-                self.tile_color = color::BLACK;
-                self.background_color = color::BLACK;
-                self.theme_alpha = 12;
+            for problem in problems {
+                eprintln!("{}: {}", path.display(), problem);
             }
-            ThemeType::Discord => {
-                self.primary_color = color::Rgb8::from_components((242, 243, 245));
-                self.secondary_color = color::Rgb8::from_components((181, 186, 193));
-                self.tile_color = color::Rgb8::from_components((43, 45, 49));
-                self.background_color = color::Rgb8::from_components((30, 31, 34));
-                self.theme_alpha = 20;
+            match themes.iter_mut().find(|other| other.name.eq_ignore_ascii_case(&theme.name)) {
+                Some(other) => *other = theme,
+                None => themes.push(theme),
             }
         }
+        Themes { themes, files, checked: Instant::now() }
+    }
+
+    /// Theme files with their modification times, sorted by path.
+    fn scan() -> Vec<(PathBuf, SystemTime)> {
+        let Ok(entries) = std::fs::read_dir(storage::config_dir().join(THEMES_DIRECTORY)) else {
+            return Vec::new();
+        };
+        let mut files: Vec<(PathBuf, SystemTime)> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|extension| extension == "toml"))
+            .filter_map(|path| Some((path.clone(), path.metadata().ok()?.modified().ok()?)))
+            .collect();
+        files.sort();
+        files
+    }
+
+    /// Reloads the themes if a file was added, changed or removed. Checks at most twice a
+    /// second and returns true if the themes were reloaded.
+    pub fn reload_if_changed(&mut self) -> bool {
+        if self.checked.elapsed() < RELOAD_INTERVAL {
+            return false;
+        }
+        self.checked = Instant::now();
+        if Themes::scan() == self.files {
+            return false;
+        }
+        *self = Themes::load();
+        true
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Theme> {
+        self.themes.iter().find(|theme| theme.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Theme `steps` after the one with the name, before it if negative. The first theme if
+    /// there is no such theme.
+    pub fn step(&self, name: &str, steps: isize) -> &Theme {
+        let position = self.themes.iter().position(|theme| theme.name.eq_ignore_ascii_case(name));
+        let count = self.themes.len() as isize;
+        &self.themes[position.map_or(0, |position| (position as isize + steps).rem_euclid(count) as usize)]
+    }
+
+    /// Theme with the name, or an error listing the themes.
+    pub fn find(&self, name: &str) -> Result<Theme, String> {
+        self.get(name).cloned().ok_or_else(|| {
+            let names: Vec<&str> = self.themes.iter().map(|theme| theme.name.as_str()).collect();
+            format!("unknown theme {}, use {}", name, names.join(", "))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn theme_file_round_trip() {
+        assert_eq!(Theme::builtin("discord").unwrap().name, "Discord");
+        for name in Theme::BUILTIN {
+            let theme = Theme::builtin(&name.to_lowercase()).unwrap();
+            let (parsed, problems) = Theme::parse(Theme::default(), &theme.to_toml(), Path::new("."));
+            assert!(problems.is_empty(), "{:?}", problems);
+            assert_eq!(parsed.to_toml(), theme.to_toml());
+        }
+    }
+
+    #[test]
+    fn theme_file_defaults() {
+        let content = "primary = \"#102030\" # comment\nsecondary = \"#abcdef\"\nplayer = \"#ff0000\"\nalpha = 300\ntile = \"#12345\"\nfont = \"missing.ttf\"\n";
+        let (theme, problems) = Theme::parse(Theme { name: "Mine".to_string(), ..Theme::default() }, content, Path::new("."));
+        assert_eq!(problems.len(), 3, "{:?}", problems);
+        assert_eq!(theme.name, "Mine");
+        assert_eq!(theme.given_color, color::Rgb8::from_components((0x10, 0x20, 0x30)));
+        assert_eq!(theme.solver_color, color::Rgb8::from_components((0xab, 0xcd, 0xef)));
+        assert_eq!(theme.player_color, color::Rgb8::from_components((255, 0, 0)));
+        assert_eq!(theme.tile_color, Theme::default().tile_color);
+        assert!(theme.font.is_none());
     }
 
-    pub fn next(&mut self) {
-        self.update(self.theme_type.next());
+    #[test]
+    fn builtin_override_keeps_digit_colors() {
+        let builtin = Theme::builtin("high contrast").unwrap();
+        let (theme, problems) = Theme::parse(builtin.clone(), "primary = \"#102030\"\n", Path::new("."));
+        assert!(problems.is_empty(), "{:?}", problems);
+        assert_eq!(theme.primary_color, color::Rgb8::from_components((0x10, 0x20, 0x30)));
+        assert_eq!(theme.player_color, builtin.player_color);
+        assert_eq!(theme.solver_color, builtin.solver_color);
+        assert_eq!(theme.highlight_color, builtin.highlight_color);
+        assert_eq!(theme.given_color, builtin.given_color);
     }
 }
@@ -0,0 +1,87 @@
+use crate::dlx::House;
+use crate::sudoku::Tile;
+
+/// Name of a tile as shown in the panel, e.g. `r9c1` for the top left tile.
+pub fn tile_name(indx: usize) -> String {
+    format!("r{}c{}", 9 - indx / 9, indx % 9 + 1)
+}
+
+/// Checks that a completed grid keeps the givens and that every house contains every digit
+/// exactly once. Returns the first problem found.
+pub fn solution(givens: &[Tile; 81], digits: &[u8; 81], houses: &[House]) -> Result<(), String> {
+    if let Some(indx) = digits.iter().position(|n| !(1..=9).contains(n)) {
+        return Err(format!("{} does not contain a digit", tile_name(indx)));
+    }
+    for (indx, (tile, n)) in givens.iter().zip(digits.iter()).enumerate() {
+        if let Tile::Const(given) = tile {
+            if given != n {
+                return Err(format!("{} contains {} instead of the given {}", tile_name(indx), n, given));
+            }
+        }
+    }
+    for house in houses.iter() {
+        for (i, a) in house.iter().enumerate() {
+            if let Some(b) = house[i + 1..].iter().find(|b| digits[**b] == digits[*a]) {
+                return Err(format!("{} and {} both contain {}", tile_name(*a), tile_name(*b), digits[*a]));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dlx;
+    use crate::sudoku::Sudoku;
+
+    const PUZZLE: &str = "530070000600195000098000060800060003400803001700020006060000280000419005000080079";
+    const SOLUTION: &str = "534678912672195348198342567859761423426853791713924856961537284287419635345286179";
+
+    fn digits(line: &str) -> [u8; 81] {
+        Sudoku::from_line(line).unwrap().to_digits()
+    }
+
+    #[test]
+    fn accepts_solution() {
+        let givens = Sudoku::from_line(PUZZLE).unwrap().tiles;
+        assert_eq!(solution(&givens, &digits(SOLUTION), &dlx::standard_houses()), Ok(()));
+    }
+
+    #[test]
+    fn rejects_empty_tile() {
+        let givens = Sudoku::from_line(PUZZLE).unwrap().tiles;
+        let mut grid = digits(SOLUTION);
+        grid[40] = 0;
+        assert!(solution(&givens, &grid, &dlx::standard_houses()).is_err());
+    }
+
+    #[test]
+    fn rejects_changed_given() {
+        // Swapping two rows keeps every house complete, but moves the givens.
+        let givens = Sudoku::from_line(PUZZLE).unwrap().tiles;
+        let mut grid = digits(SOLUTION);
+        let (first, second) = grid.split_at_mut(9);
+        first.swap_with_slice(&mut second[..9]);
+        assert!(solution(&givens, &grid, &dlx::standard_houses()).is_err());
+    }
+
+    #[test]
+    fn rejects_repeated_digit() {
+        let givens = [Tile::Empty; 81];
+        let mut grid = digits(SOLUTION);
+        grid.swap(0, 1);
+        assert!(solution(&givens, &grid, &dlx::standard_houses()).is_err());
+    }
+
+    #[test]
+    fn rejects_every_house() {
+        let givens = [Tile::Empty; 81];
+        let grid = digits(SOLUTION);
+        for house in dlx::standard_houses() {
+            let mut broken = grid;
+            broken[house[0]] = broken[house[1]];
+            assert!(solution(&givens, &broken, &dlx::standard_houses()).is_err());
+        }
+    }
+}